@@ -0,0 +1,138 @@
+//! Compares the parallel project scan with the sequential `WalkDir` loop it
+//! replaced, on a generated project of a few thousand files.
+//!
+//!     cargo bench --bench scan
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pstat::detect;
+use pstat::languages::{self, Languages, Variant};
+use pstat::stats::{self, Binaries, LangStats};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+const DIRS: usize = 40;
+const FILES_PER_DIR: usize = 100;
+
+/// Writes a fake project of Rust, Python and JavaScript sources once and reuses it.
+fn project() -> PathBuf {
+    let root = std::env::temp_dir().join("pstat-bench-project");
+    if root.join(".complete").exists() {
+        return root;
+    }
+
+    let rust = "// Adds two numbers.\nfn add(a: u32, b: u32) -> u32 {\n    a + b\n}\n\n".repeat(40);
+    let python = "# Adds two numbers.\ndef add(a, b):\n    return a + b\n\n".repeat(40);
+    let js = "/* Adds two numbers. */\nfunction add(a, b) {\n  return a + b;\n}\n\n".repeat(40);
+
+    for d in 0..DIRS {
+        let dir = root.join(format!("module{}", d));
+        fs::create_dir_all(&dir).unwrap();
+        for f in 0..FILES_PER_DIR {
+            let (ext, contents) = match f % 3 {
+                0 => ("rs", &rust),
+                1 => ("py", &python),
+                _ => ("js", &js),
+            };
+            fs::write(dir.join(format!("file{}.{}", f, ext)), contents).unwrap();
+        }
+    }
+    fs::write(root.join(".complete"), "").unwrap();
+    root
+}
+
+/// The single-threaded scan pstat used before the parallel walker, without
+/// its `git ls-files` filter, which the benchmark doesn't use either.
+fn sequential(
+    path: &Path,
+    languages: &Languages,
+) -> (
+    HashMap<String, LangStats>,
+    u64,
+    HashMap<String, u64>,
+    Binaries,
+) {
+    let mut file_counts: HashMap<String, LangStats> = HashMap::new();
+    let mut binaries = Binaries::default();
+    let mut times: HashMap<String, u64> = HashMap::new();
+    let mut proj_size: u64 = 0;
+
+    let walker = WalkDir::new(path)
+        .into_iter()
+        .filter_entry(|file| file.file_name() != ".git");
+
+    for file in walker.filter_map(|file| file.ok()) {
+        if file.metadata().unwrap().is_file() {
+            let size = file.metadata().unwrap().len();
+            let language = match languages.detect(file.path()) {
+                Some(language) => language,
+                None => {
+                    let head = detect::read_bytes(file.path(), detect::SNIFF_SIZE);
+                    if head.is_ok_and(|head| detect::is_binary(&head)) {
+                        binaries.files += 1;
+                        binaries.bytes += size;
+                    }
+                    continue;
+                }
+            };
+
+            if let Some(lc) = stats::count_lines(file.path(), &language.syntax)
+                .ok()
+                .flatten()
+            {
+                let mtime = file.metadata().unwrap().modified().unwrap();
+                let mtime = SystemTime::now()
+                    .duration_since(mtime)
+                    .unwrap_or_default()
+                    .as_secs();
+
+                times.insert(file.file_name().to_string_lossy().into_owned(), mtime);
+                proj_size += size;
+
+                let count = file_counts.entry(language.name.clone()).or_default();
+                count.category = language.category;
+                count.files += 1;
+                match languages::variant(file.path()) {
+                    Variant::Test => count.tests += 1,
+                    Variant::Minified => count.minified += 1,
+                    Variant::Source => {}
+                }
+                count.lines.add(&lc);
+                count.bytes += size;
+            } else {
+                binaries.files += 1;
+                binaries.bytes += size;
+            }
+        }
+    }
+    (file_counts, proj_size, times, binaries)
+}
+
+fn scan(c: &mut Criterion) {
+    let root = project();
+    let path = root.to_str().unwrap();
+    // The built-in table only, so that the user's definitions don't skew the results.
+    let languages = Languages::builtin();
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    let mut group = c.benchmark_group("scan");
+    group.sample_size(10);
+    group.bench_function("walkdir", |b| b.iter(|| sequential(&root, &languages)));
+    let mut thread_counts = vec![1];
+    if cpus > 1 {
+        thread_counts.push(cpus);
+    }
+    for threads in thread_counts {
+        group.bench_with_input(
+            BenchmarkId::new("parallel", threads),
+            &threads,
+            |b, &threads| b.iter(|| stats::get_stats(path, false, &languages, threads)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, scan);
+criterion_main!(benches);
@@ -0,0 +1,285 @@
+//! When commits are made: a calendar of commits per day over the last year
+//! and a punchcard of commits per weekday and hour.
+
+use crate::dates::{self, DAY};
+use crate::git;
+use git2::{Oid, Repository, Sort};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Weeks the calendar covers, a year plus the current week.
+pub const WEEKS: usize = 53;
+
+pub const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// When a non-merge commit was made and by whom, after `.mailmap` is applied.
+#[derive(Clone, Debug)]
+pub struct Commit {
+    pub name: String,
+    pub email: String,
+    /// Author time in seconds since the Unix epoch.
+    pub time: i64,
+    /// Offset of the author's time zone from UTC, in minutes.
+    pub offset: i32,
+}
+
+impl Commit {
+    /// Seconds since the epoch in the author's own time zone.
+    pub fn local_time(&self) -> i64 {
+        self.time + self.offset as i64 * 60
+    }
+}
+
+/// Every non-merge commit reachable from HEAD in the repository holding
+/// `path` that changes something under `path`, newest first. Only reads
+/// commit headers and trees, unlike `authors::contributions` which diffs
+/// every commit to count lines.
+pub fn commits(path: &str) -> Result<Vec<Commit>, String> {
+    let repo = Repository::discover(path).map_err(|e| e.message().to_string())?;
+    let prefix = git::prefix(&repo, path)?;
+    let mailmap = repo.mailmap().map_err(|e| e.message().to_string())?;
+
+    let mut walk = repo.revwalk().map_err(|e| e.message().to_string())?;
+    walk.set_sorting(Sort::TIME)
+        .map_err(|e| e.message().to_string())?;
+    if walk.push_head().is_err() {
+        // No commits yet.
+        return Ok(Vec::new());
+    }
+
+    let mut commits = Vec::new();
+    for id in walk {
+        let commit = id
+            .and_then(|id| repo.find_commit(id))
+            .map_err(|e| e.message().to_string())?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        if !prefix.as_os_str().is_empty() {
+            let parent = commit.parent(0).ok();
+            if subtree(parent.as_ref(), &prefix) == subtree(Some(&commit), &prefix) {
+                continue;
+            }
+        }
+
+        let author = commit
+            .author_with_mailmap(&mailmap)
+            .map_err(|e| e.message().to_string())?;
+        commits.push(Commit {
+            name: String::from_utf8_lossy(author.name_bytes()).into_owned(),
+            email: String::from_utf8_lossy(author.email_bytes()).into_owned(),
+            time: author.when().seconds(),
+            offset: author.when().offset_minutes(),
+        });
+    }
+    Ok(commits)
+}
+
+/// Id of the tree or blob at `prefix` in `commit`, equal between two commits
+/// exactly when nothing under `prefix` changed.
+fn subtree(commit: Option<&git2::Commit>, prefix: &Path) -> Option<Oid> {
+    let tree = commit?.tree().ok()?;
+    tree.get_path(prefix).ok().map(|entry| entry.id())
+}
+
+/// Names and emails of everyone who committed, most commits first. Emails
+/// are compared ignoring case, and the newest name of each one is kept.
+pub fn authors(commits: &[Commit]) -> Vec<(String, String)> {
+    let mut counts: HashMap<String, (usize, &Commit)> = HashMap::new();
+    for commit in commits {
+        counts
+            .entry(commit.email.to_lowercase())
+            .or_insert((0, commit))
+            .0 += 1;
+    }
+    let mut authors: Vec<(usize, &Commit)> = counts.into_values().collect();
+    authors.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+    authors
+        .into_iter()
+        .map(|(_, commit)| (commit.name.clone(), commit.email.clone()))
+        .collect()
+}
+
+/// Commits per day, in weeks starting on Monday.
+#[derive(Clone, Debug)]
+pub struct Calendar {
+    /// Days since the epoch of the Monday the first week starts on.
+    pub start: i64,
+    /// Days since the epoch of the last day counted, later days are left out.
+    pub today: i64,
+    /// Commits of each day of the week, oldest week first.
+    pub weeks: Vec<[u32; 7]>,
+}
+
+impl Calendar {
+    pub fn max(&self) -> u32 {
+        self.weeks.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u32 {
+        self.weeks.iter().flatten().sum()
+    }
+
+    /// Days since the epoch of a day in the calendar.
+    pub fn day(&self, week: usize, weekday: usize) -> i64 {
+        self.start + (week * 7 + weekday) as i64
+    }
+
+    /// The short name of the month for every week that starts a new one.
+    pub fn months(&self) -> Vec<Option<&'static str>> {
+        (0..self.weeks.len())
+            .map(|week| {
+                let month = dates::month(self.day(week, 0) * DAY);
+                let new = week > 0 && dates::month(self.day(week - 1, 0) * DAY) != month;
+                new.then(|| MONTHS[month as usize - 1])
+            })
+            .collect()
+    }
+}
+
+/// Weekday of a day count since the epoch, 0 for Monday.
+fn weekday(days: i64) -> usize {
+    // 1970-01-05 was a Monday.
+    (days - 4).rem_euclid(7) as usize
+}
+
+/// Counts the commits of the last `WEEKS` weeks up to `now`, in seconds since
+/// the epoch. Commits are placed on the day they were made in the author's
+/// time zone.
+pub fn calendar<'c>(commits: impl IntoIterator<Item = &'c Commit>, now: i64) -> Calendar {
+    let today = now.div_euclid(DAY);
+    let start = today - weekday(today) as i64 - (WEEKS as i64 - 1) * 7;
+    let mut weeks = vec![[0; 7]; WEEKS];
+    for c in commits {
+        let day = c.local_time().div_euclid(DAY);
+        if (start..=today).contains(&day) {
+            let offset = (day - start) as usize;
+            weeks[offset / 7][offset % 7] += 1;
+        }
+    }
+    Calendar {
+        start,
+        today,
+        weeks,
+    }
+}
+
+/// Commits per weekday, Monday first, and hour of the day, in the author's
+/// time zone.
+pub fn punchcard<'c>(commits: impl IntoIterator<Item = &'c Commit>) -> [[u32; 24]; 7] {
+    let mut hours = [[0; 24]; 7];
+    for c in commits {
+        let time = c.local_time();
+        let hour = time.rem_euclid(DAY) / 3600;
+        hours[weekday(time.div_euclid(DAY))][hour as usize] += 1;
+    }
+    hours
+}
+
+/// How busy a cell with `count` commits is, from 0 for none to 4 for the
+/// busiest ones, in quarters of `max`.
+pub fn level(count: u32, max: u32) -> usize {
+    if count == 0 || max == 0 {
+        return 0;
+    }
+    let quarters = (u64::from(count) * 4).div_ceil(u64::from(max));
+    quarters.min(4) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::tests::{at, commit_as, repo};
+
+    // 2024-01-01 was a Monday.
+    const MONDAY: i64 = 1_704_067_200;
+
+    fn commit(time: i64, offset: i32) -> Commit {
+        Commit {
+            name: "Ada".to_string(),
+            email: "ada@example.com".to_string(),
+            time,
+            offset,
+        }
+    }
+
+    #[test]
+    fn counts_commits_per_day() {
+        let now = MONDAY + 2 * DAY + 3600;
+        let commits = [
+            commit(MONDAY + 3600, 0),
+            commit(MONDAY + 7200, 0),
+            // Late on Tuesday in UTC, but Wednesday for the author.
+            commit(MONDAY + DAY + 23 * 3600, 120),
+            commit(MONDAY - DAY, 0),
+            commit(MONDAY - WEEKS as i64 * 7 * DAY, 0),
+            commit(now + DAY, 0),
+        ];
+        let calendar = calendar(&commits, now);
+
+        assert_eq!(calendar.weeks.len(), WEEKS);
+        assert_eq!(calendar.day(WEEKS - 1, 0), MONDAY / DAY);
+        assert_eq!(calendar.weeks[WEEKS - 1], [2, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(calendar.weeks[WEEKS - 2][6], 1);
+        assert_eq!((calendar.total(), calendar.max()), (4, 2));
+
+        let months = calendar.months();
+        assert_eq!(months[WEEKS - 1], Some("Jan"));
+        assert_eq!(months.iter().flatten().count(), 12);
+    }
+
+    #[test]
+    fn counts_commits_per_hour() {
+        let hours = punchcard(&[
+            commit(MONDAY + 9 * 3600, 0),
+            commit(MONDAY + 9 * 3600 + 60, 0),
+            commit(MONDAY - 3600, 60),
+        ]);
+        assert_eq!(hours[0][9], 2);
+        assert_eq!(hours[0][0], 1);
+        assert_eq!(hours.iter().flatten().sum::<u32>(), 3);
+    }
+
+    #[test]
+    fn reads_commit_times_under_a_path() {
+        let (root, repo) = repo("activity");
+        commit_as(&repo, &[("src/a.rs", "a\n")], "first", &at("Ada", DAY));
+        commit_as(
+            &repo,
+            &[("src/a.rs", "a\n"), ("README", "hi\n")],
+            "docs",
+            &at("Alan", 2 * DAY),
+        );
+        commit_as(
+            &repo,
+            &[("src/a.rs", "b\n"), ("README", "hi\n")],
+            "second",
+            &at("Ada", 3 * DAY),
+        );
+
+        let all = commits(root.to_str().unwrap()).unwrap();
+        let times: Vec<i64> = all.iter().map(|c| c.time).collect();
+        assert_eq!(times, [3 * DAY, 2 * DAY, DAY]);
+        let names: Vec<String> = authors(&all).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["Ada", "Alan"]);
+
+        let src = commits(root.join("src").to_str().unwrap()).unwrap();
+        let times: Vec<i64> = src.iter().map(|c| c.time).collect();
+        assert_eq!(times, [3 * DAY, DAY]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn levels_counts() {
+        assert_eq!(level(0, 10), 0);
+        assert_eq!(level(1, 10), 1);
+        assert_eq!(level(5, 10), 2);
+        assert_eq!(level(10, 10), 4);
+        assert_eq!(level(3, 0), 0);
+    }
+}
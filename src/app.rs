@@ -1,109 +1,130 @@
-use std::path::Path;
-use std::process::{Command, Stdio};
+use pstat::backend::{Branch, CommitInfo, GitBackend, LogFilter, StatusEntry};
+use pstat::dates;
+use pstat::graph::Graph;
+use pstat::report::{Change, Commit, Git};
 
-pub fn get_tree(ignore: bool, path: &str) -> String {
-    if ignore && Path::new("./.gitignore").exists() {
-        let git_ls = Command::new("git")
-            .arg("ls-tree")
-            .arg("-r")
-            .arg("--name-only")
-            .arg("HEAD")
-            .stdout(Stdio::piped())
-            .spawn()
-            .unwrap();
+/// Number of commits the Git Log loads at a time.
+pub const LOG_PAGE: usize = 100;
 
-        if cfg!(windows) {
-            let tree = Command::new("cmd")
-                .args(&["/C", "gitree", path])
-                .output()
-                .expect("gitree failed");
-            return String::from_utf8(tree.stdout).unwrap();
-        } else {
-            let tree = Command::new("tree")
-                .arg("--fromfile")
-                .stdin(Stdio::from(git_ls.stdout.unwrap()))
-                .stdout(Stdio::piped())
-                .spawn()
-                .unwrap();
-            return String::from_utf8(tree.wait_with_output().unwrap().stdout).unwrap();
-        };
-
-    } else {
-        if cfg!(windows) {
-            let output1 = Command::new("cmd")
-                .args(&["/C", "TREE", path, "/f", "/a"]) 
-                .output()
-                .expect("Tree command failed here");
-            return String::from_utf8(output1.stdout).unwrap();
-        } else {
-            let output1 = Command::new("tree")
-                .arg(path)
-                .output()
-                .expect("Tree command failed");
-            return String::from_utf8(output1.stdout).unwrap();
-        }
-    }
+/// What the Git tab shows, read once at startup.
+pub struct GitState {
+    /// The checked out branch, `None` on a detached HEAD.
+    pub head: Option<String>,
+    /// The branch the others are compared against.
+    pub default_branch: Option<String>,
+    /// Local and remote-tracking branches.
+    pub branches: Vec<Branch>,
+    pub status: Vec<StatusEntry>,
+    pub log: Log,
 }
 
-pub fn get_branches(path: &str) -> String {
-    let branches = Command::new("git")
-        .arg("-C")
-        .arg(path)
-        .arg("branch")
-        .output()
-        .expect("git branch command failed");
+/// The commits of the Git Log matching a filter, newest first, loaded a page
+/// at a time as the cursor reaches the end.
+pub struct Log {
+    pub filter: LogFilter,
+    pub commits: Vec<CommitInfo>,
+    /// The graph in front of each commit.
+    pub graph: Vec<String>,
+    lanes: Graph,
+    /// Whether all matching commits are loaded.
+    pub complete: bool,
+    /// Why loading the last page failed.
+    pub error: Option<String>,
+}
 
-    let mut branches = String::from_utf8(branches.stdout).unwrap();
-    if branches == "" {
-        branches = String::from("Not a git repository. No branches found.");
+impl Log {
+    pub fn new(filter: LogFilter) -> Log {
+        Log {
+            filter,
+            commits: Vec::new(),
+            graph: Vec::new(),
+            lanes: Graph::default(),
+            complete: false,
+            error: None,
+        }
     }
-    branches
-}
 
-pub fn get_log_tree(path: &str) -> String {
-    let log = Command::new("git")
-        .arg("-C")
-        .arg(path)
-        .arg("log")
-        .arg("-n 20")
-        .arg("--graph")
-        .arg("--pretty=oneline")
-        .arg("--abbrev-commit")
-        .output()
-        .expect("git log command failed");
-        let mut log = String::from_utf8(log.stdout).unwrap();
-    if log == "" {
-        log = String::from("Not a git repository. No log found.");
+    /// Loads the next `LOG_PAGE` commits, unless all of them are loaded.
+    pub fn load_more(&mut self, git: &(impl GitBackend + ?Sized)) {
+        if self.complete {
+            return;
+        }
+        let after = self.commits.last().map(|commit| commit.id.as_str());
+        let page = match git.log(&self.filter, after, LOG_PAGE) {
+            Ok(page) => page,
+            Err(e) => {
+                self.error = Some(e);
+                self.complete = true;
+                return;
+            }
+        };
+        self.complete = page.len() < LOG_PAGE;
+        for commit in page {
+            // The parents of filtered commits are mostly left out, so their
+            // lanes would never end.
+            let parents = match self.filter.is_empty() {
+                true => &commit.parents[..],
+                false => &[],
+            };
+            self.graph.push(self.lanes.row(&commit.id, parents));
+            self.commits.push(commit);
+        }
     }
-    log
 }
 
-pub fn get_log(path: &str) -> String {
-    let log = Command::new("git")
-        .arg("-C")
-        .arg(path)
-        .arg("log")
-        .arg("-n 5")
-        .output()
-        .expect("git log command failed");
-    let mut log = String::from_utf8(log.stdout).unwrap();
-    if log == "" {
-        log = String::from("Not a git repository. No log found.");
+/// Reads branches, uncommitted changes and the first page of the log for the
+/// Git tab.
+pub fn get_git_state(git: &impl GitBackend) -> Result<GitState, String> {
+    let mut log = Log::new(LogFilter::default());
+    log.load_more(git);
+    if let Some(e) = log.error {
+        return Err(e);
     }
-    log
+    Ok(GitState {
+        head: git.head(),
+        default_branch: git.default_branch(),
+        branches: git.branches()?,
+        status: git.status()?,
+        log,
+    })
 }
 
-pub fn get_status(path: &str) -> String {
-    let status = Command::new("git")
-        .arg("-C")
-        .arg(path)
-        .arg("status")
-        .output()
-        .expect("git status command failed");
-    let mut status = String::from_utf8(status.stdout).unwrap();
+/// Collects branches, uncommitted changes and the last `n` commits for the
+/// report.
+pub fn get_git(git: &impl GitBackend, n: usize) -> Git {
+    let status = git
+        .status()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| Change {
+            status: entry.code(),
+            path: entry.path,
+        })
+        .collect();
+
+    let log = git
+        .log(&LogFilter::default(), None, n)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|commit| Commit {
+            date: dates::rfc3339(commit.time, commit.offset),
+            id: commit.id,
+            author: commit.author,
+            email: commit.email,
+            summary: commit.summary,
+        })
+        .collect();
 
-    if status == "" {
-        status = String::from("Not a git repository. No status found.");
+    Git {
+        branch: git.head(),
+        branches: git
+            .branches()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|branch| !branch.remote)
+            .map(|branch| branch.name)
+            .collect(),
+        status,
+        log,
     }
-    status
 }
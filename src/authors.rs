@@ -0,0 +1,376 @@
+use crate::{dates, export, git};
+use git2::{DiffOptions, Repository, Sort};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+
+/// Version of the per-commit cache, bumped when the way lines are counted changes.
+const CACHE_VERSION: u32 = 1;
+
+/// A non-merge commit, by its author after `.mailmap` is applied.
+#[derive(Clone, Debug)]
+pub struct Contribution {
+    pub name: String,
+    pub email: String,
+    /// Author time in seconds since the Unix epoch.
+    pub time: i64,
+    /// Offset of the author's time zone from UTC, in minutes.
+    pub offset: i32,
+    pub added: u32,
+    pub removed: u32,
+}
+
+impl Contribution {
+    /// Seconds since the epoch in the author's own time zone.
+    pub fn local_time(&self) -> i64 {
+        self.time + self.offset as i64 * 60
+    }
+}
+
+/// Line changes of already diffed commits, so that later runs only diff new
+/// commits. Added lines, removed lines and changed files by commit id, per
+/// scanned directory.
+type Cache = git::Cache<HashMap<String, HashMap<String, [u32; 3]>>>;
+
+/// Every non-merge commit reachable from HEAD in the repository holding
+/// `path` that changes something under `path`, newest first.
+pub fn contributions(path: &str) -> Result<Vec<Contribution>, String> {
+    let repo = Repository::discover(path).map_err(|e| e.message().to_string())?;
+    let prefix = git::prefix(&repo, path)?;
+    let mailmap = repo.mailmap().map_err(|e| e.message().to_string())?;
+
+    let mut cache = Cache::load(&repo, "authors.json", CACHE_VERSION);
+    let key = prefix.to_string_lossy().into_owned();
+    let cached = cache.entries.entry(key).or_default();
+
+    let mut walk = repo.revwalk().map_err(|e| e.message().to_string())?;
+    walk.set_sorting(Sort::TIME)
+        .map_err(|e| e.message().to_string())?;
+    if walk.push_head().is_err() {
+        // No commits yet.
+        return Ok(Vec::new());
+    }
+
+    let mut contributions = Vec::new();
+    for id in walk {
+        let commit = id
+            .and_then(|id| repo.find_commit(id))
+            .map_err(|e| e.message().to_string())?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+
+        let id = commit.id().to_string();
+        let [added, removed, files] = match cached.get(&id) {
+            Some(&counts) => counts,
+            None => {
+                let mut options = DiffOptions::new();
+                if !prefix.as_os_str().is_empty() {
+                    options.pathspec(&prefix);
+                }
+                let parent = commit.parent(0).ok().and_then(|p| p.tree().ok());
+                let tree = commit.tree().ok();
+                let counts = repo
+                    .diff_tree_to_tree(parent.as_ref(), tree.as_ref(), Some(&mut options))
+                    .and_then(|diff| diff.stats())
+                    .map(|stats| {
+                        [
+                            stats.insertions() as u32,
+                            stats.deletions() as u32,
+                            stats.files_changed() as u32,
+                        ]
+                    })
+                    .map_err(|e| e.message().to_string())?;
+                cached.insert(id, counts);
+                counts
+            }
+        };
+        if files == 0 && !prefix.as_os_str().is_empty() {
+            continue;
+        }
+
+        let author = commit
+            .author_with_mailmap(&mailmap)
+            .map_err(|e| e.message().to_string())?;
+        contributions.push(Contribution {
+            name: String::from_utf8_lossy(author.name_bytes()).into_owned(),
+            email: String::from_utf8_lossy(author.email_bytes()).into_owned(),
+            time: author.when().seconds(),
+            offset: author.when().offset_minutes(),
+            added,
+            removed,
+        });
+    }
+
+    cache.save();
+    Ok(contributions)
+}
+
+/// Column the authors table is sorted by. Names sort alphabetically, dates
+/// most recent first and everything else largest first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AuthorSort {
+    Name,
+    #[default]
+    Commits,
+    Added,
+    Removed,
+    First,
+    Last,
+    Days,
+}
+
+impl AuthorSort {
+    pub const NAMES: [&'static str; 7] = [
+        "name", "commits", "added", "removed", "first", "last", "days",
+    ];
+
+    /// The next column, for cycling through them in the TUI.
+    pub fn next(self) -> AuthorSort {
+        let i = AuthorSort::NAMES
+            .iter()
+            .position(|name| *name == self.name())
+            .unwrap_or(0);
+        AuthorSort::NAMES[(i + 1) % AuthorSort::NAMES.len()]
+            .parse()
+            .unwrap_or_default()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AuthorSort::Name => "name",
+            AuthorSort::Commits => "commits",
+            AuthorSort::Added => "added",
+            AuthorSort::Removed => "removed",
+            AuthorSort::First => "first",
+            AuthorSort::Last => "last",
+            AuthorSort::Days => "days",
+        }
+    }
+}
+
+impl FromStr for AuthorSort {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<AuthorSort, String> {
+        match name {
+            "name" => Ok(AuthorSort::Name),
+            "commits" => Ok(AuthorSort::Commits),
+            "added" => Ok(AuthorSort::Added),
+            "removed" => Ok(AuthorSort::Removed),
+            "first" => Ok(AuthorSort::First),
+            "last" => Ok(AuthorSort::Last),
+            "days" => Ok(AuthorSort::Days),
+            _ => Err(format!("Unknown sort column: {}", name)),
+        }
+    }
+}
+
+/// What one person contributed over a time range.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Author {
+    pub name: String,
+    pub email: String,
+    pub commits: u32,
+    pub added: u32,
+    pub removed: u32,
+    /// Dates of the first and last commit in the author's time zone,
+    /// `YYYY-MM-DD`, the same days `active_days` counts.
+    pub first: String,
+    pub last: String,
+    /// Number of distinct days with at least one commit.
+    pub active_days: u32,
+}
+
+/// Sums up contributions per author, only counting commits made from
+/// `since` (inclusive) until `until` (exclusive), in seconds since the epoch.
+pub fn authors(
+    contributions: &[Contribution],
+    since: Option<i64>,
+    until: Option<i64>,
+    sort: AuthorSort,
+) -> Vec<Author> {
+    struct Totals<'c> {
+        latest: &'c Contribution,
+        commits: u32,
+        added: u32,
+        removed: u32,
+        first: &'c Contribution,
+        last: &'c Contribution,
+        days: BTreeSet<i64>,
+    }
+
+    // Identities are merged by email, taking the name of the latest commit.
+    let mut totals: HashMap<String, Totals> = HashMap::new();
+    let in_range = |c: &&Contribution| {
+        since.is_none_or(|since| c.time >= since) && until.is_none_or(|until| c.time < until)
+    };
+    for c in contributions.iter().filter(in_range) {
+        let t = totals.entry(c.email.to_lowercase()).or_insert(Totals {
+            latest: c,
+            commits: 0,
+            added: 0,
+            removed: 0,
+            first: c,
+            last: c,
+            days: BTreeSet::new(),
+        });
+        if c.time > t.latest.time {
+            t.latest = c;
+        }
+        t.commits += 1;
+        t.added += c.added;
+        t.removed += c.removed;
+        if c.time < t.first.time {
+            t.first = c;
+        }
+        if c.time > t.last.time {
+            t.last = c;
+        }
+        t.days.insert(c.local_time().div_euclid(dates::DAY));
+    }
+
+    let mut authors: Vec<(Author, i64, i64)> = totals
+        .into_values()
+        .map(|t| {
+            let author = Author {
+                name: t.latest.name.clone(),
+                email: t.latest.email.clone(),
+                commits: t.commits,
+                added: t.added,
+                removed: t.removed,
+                first: dates::format(t.first.local_time()),
+                last: dates::format(t.last.local_time()),
+                active_days: t.days.len() as u32,
+            };
+            (author, t.first.time, t.last.time)
+        })
+        .collect();
+
+    authors.sort_by(|(a, a_first, a_last), (b, b_first, b_last)| {
+        let by = match sort {
+            AuthorSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            AuthorSort::Commits => b.commits.cmp(&a.commits),
+            AuthorSort::Added => b.added.cmp(&a.added),
+            AuthorSort::Removed => b.removed.cmp(&a.removed),
+            AuthorSort::First => b_first.cmp(a_first),
+            AuthorSort::Last => b_last.cmp(a_last),
+            AuthorSort::Days => b.active_days.cmp(&a.active_days),
+        };
+        by.then_with(|| a.name.cmp(&b.name))
+    });
+    authors.into_iter().map(|(author, _, _)| author).collect()
+}
+
+fn rows(authors: &[Author]) -> Vec<Vec<String>> {
+    let header = [
+        "Author", "Email", "Commits", "Added", "Removed", "First", "Last", "Days",
+    ];
+    let mut rows = vec![header.iter().map(|h| h.to_string()).collect()];
+    for a in authors {
+        rows.push(vec![
+            a.name.clone(),
+            a.email.clone(),
+            a.commits.to_string(),
+            a.added.to_string(),
+            a.removed.to_string(),
+            a.first.clone(),
+            a.last.clone(),
+            a.active_days.to_string(),
+        ]);
+    }
+    rows
+}
+
+/// Renders the authors as a plain text table.
+pub fn text(authors: &[Author]) -> String {
+    if authors.is_empty() {
+        return "No commits found.\n".to_string();
+    }
+    export::text_rows(&rows(authors))
+}
+
+pub fn markdown(authors: &[Author]) -> String {
+    export::markdown_rows(&rows(authors))
+}
+
+pub fn csv(authors: &[Author]) -> String {
+    export::csv_rows(&rows(authors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::tests::{at, commit_as, repo};
+    use git2::{Signature, Time};
+    use std::fs;
+
+    #[test]
+    fn merges_identities_with_mailmap() {
+        let (root, repo) = repo("authors");
+        let mailmap = (
+            ".mailmap",
+            "Ada Lovelace <ada.lovelace@example.com> <countess@example.org>\n",
+        );
+        // Half past midnight UTC, but still the day before an hour west.
+        let time = Time::new(2 * dates::DAY + 30 * 60, -60);
+        let renamed = Signature::new("A. L.", "countess@example.org", &time).unwrap();
+        commit_as(
+            &repo,
+            &[mailmap, ("a.txt", "1\n2\n")],
+            "first",
+            &at("Ada Lovelace", 0),
+        );
+        commit_as(
+            &repo,
+            &[mailmap, ("a.txt", "1\n2\n3\n")],
+            "second",
+            &renamed,
+        );
+        commit_as(
+            &repo,
+            &[mailmap, ("a.txt", "1\n3\n")],
+            "third",
+            &at("Alan Turing", 10 * dates::DAY),
+        );
+
+        let contributions = contributions(root.to_str().unwrap()).unwrap();
+        assert_eq!(contributions.len(), 3);
+
+        let all = authors(&contributions, None, None, AuthorSort::Commits);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].name, "Ada Lovelace");
+        assert_eq!(all[0].email, "ada.lovelace@example.com");
+        assert_eq!(
+            (
+                all[0].commits,
+                all[0].added,
+                all[0].removed,
+                all[0].active_days
+            ),
+            (2, 4, 0, 2)
+        );
+        assert_eq!(
+            (all[0].first.as_str(), all[0].last.as_str()),
+            ("1970-01-01", "1970-01-02")
+        );
+        assert_eq!((all[1].commits, all[1].added, all[1].removed), (1, 0, 1));
+
+        let sorted = authors(&contributions, None, None, AuthorSort::Removed);
+        assert_eq!(sorted[0].name, "Alan Turing");
+
+        let later = authors(
+            &contributions,
+            Some(dates::DAY),
+            Some(3 * dates::DAY),
+            AuthorSort::Name,
+        );
+        assert_eq!(later.len(), 1);
+        assert_eq!(
+            (later[0].name.as_str(), later[0].commits),
+            ("Ada Lovelace", 1)
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,968 @@
+//! Reading branches, uncommitted changes and commits of a repository as
+//! typed data, for the Git tab and the report to format themselves.
+
+use crate::dates;
+use git2::{
+    BranchType, Delta, Diff, DiffFindOptions, DiffOptions, FileMode, Oid, Patch, Repository, Sort,
+    Status, StatusOptions,
+};
+use std::cell::{Ref, RefCell};
+use std::collections::{HashMap, HashSet};
+
+/// A local or remote-tracking branch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Branch {
+    pub name: String,
+    /// Whether the branch is checked out.
+    pub head: bool,
+    /// Whether this is a remote-tracking branch, like `origin/main`.
+    pub remote: bool,
+    /// The branch a local branch is set up to follow, even if it was deleted
+    /// since.
+    pub upstream: Option<String>,
+    /// Commits the branch has that its upstream doesn't, and the other way
+    /// round. `None` without an upstream or once it is gone.
+    pub ahead_behind: Option<(usize, usize)>,
+    /// The commit the branch points to, `None` before the first commit.
+    pub last: Option<CommitInfo>,
+    /// Whether the branch's commit is part of the default branch.
+    pub merged: bool,
+}
+
+/// An uncommitted change to a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusEntry {
+    /// Path relative to the repository root.
+    pub path: String,
+    /// The path before a rename.
+    pub from: Option<String>,
+    /// Status letters of the index and the working tree, like in
+    /// `git status --porcelain`: ' ' for unchanged, `M`, `A`, `D`, `R`, `T`,
+    /// `?` for untracked and `U` for conflicts.
+    pub index: char,
+    pub worktree: char,
+    /// Whether the path is a submodule, whose changes are commits rather
+    /// than lines.
+    pub submodule: bool,
+    /// Lines changed between HEAD and the index.
+    pub staged: LineChanges,
+    /// Lines changed between the index and the working tree, all lines of
+    /// an untracked file.
+    pub unstaged: LineChanges,
+}
+
+/// Lines added and removed in a file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LineChanges {
+    pub added: u32,
+    pub removed: u32,
+}
+
+/// The sections of a `git status`, in the order they are shown. A file
+/// with staged and unstaged changes is in both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StatusGroup {
+    Conflicted,
+    Staged,
+    Unstaged,
+    Untracked,
+}
+
+impl StatusGroup {
+    pub const ALL: [StatusGroup; 4] = [
+        StatusGroup::Conflicted,
+        StatusGroup::Staged,
+        StatusGroup::Unstaged,
+        StatusGroup::Untracked,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusGroup::Conflicted => "Conflicted",
+            StatusGroup::Staged => "Staged",
+            StatusGroup::Unstaged => "Unstaged",
+            StatusGroup::Untracked => "Untracked",
+        }
+    }
+}
+
+impl StatusEntry {
+    /// The two-letter porcelain status, e.g. ` M` or `??`.
+    pub fn code(&self) -> String {
+        format!("{}{}", self.index, self.worktree)
+    }
+
+    pub fn in_group(&self, group: StatusGroup) -> bool {
+        match group {
+            StatusGroup::Conflicted => self.index == 'U',
+            StatusGroup::Staged => !matches!(self.index, ' ' | '?' | 'U'),
+            StatusGroup::Unstaged => !matches!(self.worktree, ' ' | '?' | 'U'),
+            StatusGroup::Untracked => self.index == '?',
+        }
+    }
+
+    /// The status letter and changed lines of the entry in `group`.
+    pub fn change(&self, group: StatusGroup) -> (char, LineChanges) {
+        match group {
+            StatusGroup::Staged => (self.index, self.staged),
+            _ => (self.worktree, self.unstaged),
+        }
+    }
+}
+
+/// A commit, without its changes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommitInfo {
+    pub id: String,
+    pub parents: Vec<String>,
+    pub author: String,
+    pub email: String,
+    /// Author time in seconds since the Unix epoch.
+    pub time: i64,
+    /// Offset of the author's time zone from UTC, in minutes.
+    pub offset: i32,
+    pub summary: String,
+}
+
+impl CommitInfo {
+    /// The abbreviated commit id.
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(7)]
+    }
+}
+
+/// Which commits a log lists. The default lists all of them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Text the message, author name or email has to contain, ignoring case,
+    /// or the start of the commit id.
+    pub text: Option<String>,
+    /// Text the author name or email has to contain, ignoring case.
+    pub author: Option<String>,
+    /// File or directory the commit has to change, relative to the
+    /// repository root.
+    pub path: Option<String>,
+    /// Author time bounds in seconds since the Unix epoch, `until` excluded.
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+impl LogFilter {
+    /// Parses a search like `fix typo author:ada path:src since:2024-01-01`.
+    /// Words with an `author:`, `path:`, `since:` or `until:` prefix set that
+    /// filter, with both dates included, and the other words are searched
+    /// for together.
+    pub fn parse(query: &str) -> Result<LogFilter, String> {
+        let mut filter = LogFilter::default();
+        let mut words = Vec::new();
+        for word in query.split_whitespace() {
+            let date = |date: &str| {
+                dates::parse(date)
+                    .ok_or_else(|| format!("Invalid date {}, expected YYYY-MM-DD.", date))
+            };
+            match word.split_once(':') {
+                Some(("author", author)) => filter.author = Some(author.to_string()),
+                Some(("path", path)) => filter.path = Some(path.trim_end_matches('/').to_string()),
+                Some(("since", since)) => filter.since = Some(date(since)?),
+                Some(("until", until)) => filter.until = Some(date(until)? + dates::DAY),
+                _ => words.push(word),
+            }
+        }
+        if !words.is_empty() {
+            filter.text = Some(words.join(" "));
+        }
+        Ok(filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == LogFilter::default()
+    }
+}
+
+/// A commit with its full message and changes, for the commit detail view.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommitDetail {
+    pub info: CommitInfo,
+    pub message: String,
+    pub committer: String,
+    pub committer_email: String,
+    /// Commit time in seconds since the Unix epoch.
+    pub commit_time: i64,
+    /// Offset of the committer's time zone from UTC, in minutes.
+    pub commit_offset: i32,
+    /// Changes compared to the first parent, or to an empty tree for the
+    /// first commit.
+    pub files: Vec<FileDiff>,
+}
+
+/// The changes a commit made to one file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileDiff {
+    pub path: String,
+    /// The path before a rename.
+    pub from: Option<String>,
+    /// `A`, `M`, `D`, `R` or `T`, like in `git show --name-status`.
+    pub status: char,
+    pub changes: LineChanges,
+    pub binary: bool,
+    /// The unified diff, hunk headers included.
+    pub lines: Vec<DiffLine>,
+}
+
+/// A line of a unified diff.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffLine {
+    /// `+` for added, `-` for removed, ` ` for context lines and `@` for
+    /// hunk headers.
+    pub origin: char,
+    /// The line without its origin or line break.
+    pub content: String,
+}
+
+/// Where the Git tab and the report get their data from.
+pub trait GitBackend {
+    /// Name of the checked out branch, `None` on a detached HEAD.
+    fn head(&self) -> Option<String>;
+
+    /// The branch others get merged into: the one `origin/HEAD` points to,
+    /// or else `main` or `master`, or else the checked out one.
+    fn default_branch(&self) -> Option<String>;
+
+    /// Local branches by name, then remote-tracking ones.
+    fn branches(&self) -> Result<Vec<Branch>, String>;
+
+    /// Uncommitted changes, untracked files included and ignored ones left out.
+    fn status(&self) -> Result<Vec<StatusEntry>, String>;
+
+    /// Up to `n` commits reachable from HEAD and matching `filter`, newest
+    /// first with parents after their children. Starts after the commit
+    /// `after`, the last one of the previous page, or at HEAD without it.
+    /// Empty without commits.
+    fn log(
+        &self,
+        filter: &LogFilter,
+        after: Option<&str>,
+        n: usize,
+    ) -> Result<Vec<CommitInfo>, String>;
+
+    /// Number of commits reachable from HEAD.
+    fn commit_count(&self) -> Result<usize, String>;
+
+    /// The commit `id` with its changes.
+    fn commit(&self, id: &str) -> Result<CommitDetail, String>;
+}
+
+/// Reads the repository in process with libgit2.
+pub struct Git2Backend {
+    repo: Repository,
+    /// The commits of the log, so that later pages start where the previous
+    /// one ended instead of walking the history from HEAD again.
+    order: RefCell<Option<Order>>,
+}
+
+/// Commits reachable from HEAD in log order.
+struct Order {
+    head: Oid,
+    ids: Vec<Oid>,
+    /// Position of every commit in `ids`.
+    positions: HashMap<Oid, usize>,
+}
+
+fn message(e: git2::Error) -> String {
+    e.message().to_string()
+}
+
+impl Git2Backend {
+    /// Opens the repository holding `path`.
+    pub fn open(path: &str) -> Result<Git2Backend, String> {
+        let repo = Repository::discover(path).map_err(message)?;
+        Ok(Git2Backend {
+            repo,
+            order: RefCell::new(None),
+        })
+    }
+
+    /// Lines changed per file between HEAD and the index and between the
+    /// index and the working tree, untracked files included, by new path.
+    fn line_changes(&self) -> Result<[HashMap<String, LineChanges>; 2], git2::Error> {
+        let head = self.repo.head().and_then(|head| head.peel_to_tree()).ok();
+        let staged = self.repo.diff_tree_to_index(head.as_ref(), None, None)?;
+        let unstaged = self.repo.diff_index_to_workdir(
+            None,
+            Some(
+                DiffOptions::new()
+                    .include_untracked(true)
+                    .recurse_untracked_dirs(true)
+                    .show_untracked_content(true),
+            ),
+        )?;
+        Ok([lines_by_path(staged)?, lines_by_path(unstaged)?])
+    }
+
+    fn matches(&self, commit: &git2::Commit, filter: &LogFilter) -> Result<bool, git2::Error> {
+        let author = commit.author();
+        let time = author.when().seconds();
+        if filter.since.is_some_and(|since| time < since)
+            || filter.until.is_some_and(|until| time >= until)
+        {
+            return Ok(false);
+        }
+
+        let name = String::from_utf8_lossy(author.name_bytes()).to_lowercase();
+        let email = String::from_utf8_lossy(author.email_bytes()).to_lowercase();
+        if let Some(author) = &filter.author {
+            let author = author.to_lowercase();
+            if !name.contains(&author) && !email.contains(&author) {
+                return Ok(false);
+            }
+        }
+        if let Some(text) = &filter.text {
+            let text = text.to_lowercase();
+            let message = String::from_utf8_lossy(commit.message_bytes()).to_lowercase();
+            if !message.contains(&text)
+                && !name.contains(&text)
+                && !email.contains(&text)
+                && !commit.id().to_string().starts_with(&text)
+            {
+                return Ok(false);
+            }
+        }
+
+        match &filter.path {
+            Some(path) => {
+                let parent = commit.parent(0).ok().map(|p| p.tree()).transpose()?;
+                let diff = self.repo.diff_tree_to_tree(
+                    parent.as_ref(),
+                    Some(&commit.tree()?),
+                    Some(DiffOptions::new().pathspec(path)),
+                )?;
+                Ok(diff.deltas().len() > 0)
+            }
+            None => Ok(true),
+        }
+    }
+
+    /// The log order of the commits reachable from HEAD, walked again only
+    /// when HEAD moved. `None` without commits.
+    fn order(&self) -> Result<Option<Ref<'_, Order>>, String> {
+        let head = match self.repo.head().ok().and_then(|head| head.target()) {
+            Some(head) => head,
+            None => return Ok(None),
+        };
+        if self.order.borrow().as_ref().map(|order| order.head) != Some(head) {
+            let mut walk = self.repo.revwalk().map_err(message)?;
+            walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)
+                .map_err(message)?;
+            walk.push(head).map_err(message)?;
+            let ids = walk.collect::<Result<Vec<Oid>, _>>().map_err(message)?;
+            let positions = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+            *self.order.borrow_mut() = Some(Order {
+                head,
+                ids,
+                positions,
+            });
+        }
+        Ok(Some(Ref::map(self.order.borrow(), |order| {
+            order.as_ref().expect("order was just read")
+        })))
+    }
+}
+
+fn lines_by_path(mut diff: Diff) -> Result<HashMap<String, LineChanges>, git2::Error> {
+    diff.find_similar(Some(
+        DiffFindOptions::new().renames(true).for_untracked(true),
+    ))?;
+    let mut lines = HashMap::new();
+    for (i, delta) in diff.deltas().enumerate() {
+        let file = delta.new_file();
+        if file.mode() == FileMode::Commit {
+            continue;
+        }
+        let path = match file.path() {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None => continue,
+        };
+        // Binary files have no patch and count no lines.
+        if let Some(patch) = Patch::from_diff(&diff, i)? {
+            let (_, added, removed) = patch.line_stats()?;
+            let changes = LineChanges {
+                added: added as u32,
+                removed: removed as u32,
+            };
+            lines.insert(path, changes);
+        }
+    }
+    Ok(lines)
+}
+
+fn commit_info(commit: &git2::Commit) -> CommitInfo {
+    let author = commit.author();
+    CommitInfo {
+        id: commit.id().to_string(),
+        parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+        author: String::from_utf8_lossy(author.name_bytes()).into_owned(),
+        email: String::from_utf8_lossy(author.email_bytes()).into_owned(),
+        time: author.when().seconds(),
+        offset: author.when().offset_minutes(),
+        summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned(),
+    }
+}
+
+fn file_diff(diff: &Diff, i: usize) -> Result<FileDiff, git2::Error> {
+    let delta = diff.get_delta(i).expect("delta index in range");
+    let path = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().into_owned());
+    let new = path(delta.new_file());
+    let old = path(delta.old_file());
+    let status = match delta.status() {
+        Delta::Added => 'A',
+        Delta::Deleted => 'D',
+        Delta::Renamed => 'R',
+        Delta::Typechange => 'T',
+        _ => 'M',
+    };
+
+    let mut file = FileDiff {
+        path: new.clone().or(old.clone()).unwrap_or_default(),
+        from: old.filter(|old| status == 'R' && Some(old) != new.as_ref()),
+        status,
+        changes: LineChanges::default(),
+        binary: delta.flags().is_binary(),
+        lines: Vec::new(),
+    };
+    let patch = match Patch::from_diff(diff, i)? {
+        Some(patch) => patch,
+        None => return Ok(file),
+    };
+    let (_, added, removed) = patch.line_stats()?;
+    file.changes = LineChanges {
+        added: added as u32,
+        removed: removed as u32,
+    };
+    for h in 0..patch.num_hunks() {
+        let (hunk, lines) = patch.hunk(h)?;
+        let header = String::from_utf8_lossy(hunk.header());
+        file.lines.push(DiffLine {
+            origin: '@',
+            content: header.trim_end().to_string(),
+        });
+        for l in 0..lines {
+            let line = patch.line_in_hunk(h, l)?;
+            let content = String::from_utf8_lossy(line.content());
+            file.lines.push(DiffLine {
+                origin: match line.origin() {
+                    '+' | '-' => line.origin(),
+                    _ => ' ',
+                },
+                content: content.trim_end_matches(['\n', '\r']).to_string(),
+            });
+        }
+    }
+    Ok(file)
+}
+
+/// Porcelain letters of the index and working tree parts of `status`.
+fn status_letters(status: Status) -> (char, char) {
+    if status.is_conflicted() {
+        return ('U', 'U');
+    }
+    if status.is_wt_new() {
+        return ('?', '?');
+    }
+    let index = match status {
+        s if s.is_index_new() => 'A',
+        s if s.is_index_modified() => 'M',
+        s if s.is_index_deleted() => 'D',
+        s if s.is_index_renamed() => 'R',
+        s if s.is_index_typechange() => 'T',
+        _ => ' ',
+    };
+    let worktree = match status {
+        s if s.is_wt_modified() => 'M',
+        s if s.is_wt_deleted() => 'D',
+        s if s.is_wt_renamed() => 'R',
+        s if s.is_wt_typechange() => 'T',
+        _ => ' ',
+    };
+    (index, worktree)
+}
+
+impl GitBackend for Git2Backend {
+    fn head(&self) -> Option<String> {
+        // HEAD of a repository without commits points to a branch that doesn't exist yet.
+        let head = self.repo.find_reference("HEAD").ok()?;
+        let target = head.symbolic_target().ok()??;
+        target.strip_prefix("refs/heads/").map(String::from)
+    }
+
+    fn default_branch(&self) -> Option<String> {
+        let origin = self.repo.find_reference("refs/remotes/origin/HEAD").ok();
+        let target = origin
+            .as_ref()
+            .and_then(|head| head.symbolic_target().ok()?);
+        if let Some(name) = target.and_then(|target| target.strip_prefix("refs/remotes/")) {
+            return Some(name.to_string());
+        }
+        ["main", "master"]
+            .into_iter()
+            .find(|name| self.repo.find_branch(name, BranchType::Local).is_ok())
+            .map(String::from)
+            .or_else(|| self.head())
+    }
+
+    fn branches(&self) -> Result<Vec<Branch>, String> {
+        let default = self
+            .default_branch()
+            .and_then(|name| self.repo.resolve_reference_from_short_name(&name).ok())
+            .and_then(|reference| reference.peel_to_commit().ok())
+            .map(|commit| commit.id());
+
+        let mut branches = Vec::new();
+        for branch in self.repo.branches(None).map_err(message)? {
+            let (branch, kind) = branch.map_err(message)?;
+            let name = match branch.name() {
+                Ok(Some(name)) => name.to_string(),
+                _ => continue,
+            };
+            let remote = kind == BranchType::Remote;
+            // `origin/HEAD` only points to another remote-tracking branch.
+            if remote && name.ends_with("/HEAD") {
+                continue;
+            }
+
+            let tip = branch.get().peel_to_commit().ok();
+            let upstream = match (remote, branch.get().name()) {
+                (false, Ok(refname)) => self.repo.branch_upstream_name(refname).ok(),
+                _ => None,
+            };
+            let upstream = upstream
+                .as_ref()
+                .and_then(|upstream| upstream.as_str().ok());
+            let ahead_behind = match (&tip, upstream) {
+                (Some(tip), Some(upstream)) => self
+                    .repo
+                    .refname_to_id(upstream)
+                    .and_then(|upstream| self.repo.graph_ahead_behind(tip.id(), upstream))
+                    .ok(),
+                _ => None,
+            };
+            let merged = match (&tip, default) {
+                (Some(tip), Some(default)) => {
+                    tip.id() == default
+                        || self
+                            .repo
+                            .graph_descendant_of(default, tip.id())
+                            .unwrap_or(false)
+                }
+                _ => false,
+            };
+
+            branches.push(Branch {
+                name,
+                head: branch.is_head(),
+                remote,
+                upstream: upstream.map(|upstream| {
+                    let short = upstream.strip_prefix("refs/remotes/");
+                    let short = short.or_else(|| upstream.strip_prefix("refs/heads/"));
+                    short.unwrap_or(upstream).to_string()
+                }),
+                ahead_behind,
+                last: tip.as_ref().map(commit_info),
+                merged,
+            });
+        }
+        // Branches of a repository without commits only exist as HEAD.
+        if let (true, Some(name)) = (branches.is_empty(), self.head()) {
+            branches.push(Branch {
+                name,
+                head: true,
+                remote: false,
+                upstream: None,
+                ahead_behind: None,
+                last: None,
+                merged: false,
+            });
+        }
+        branches.sort_by(|a, b| (a.remote, &a.name).cmp(&(b.remote, &b.name)));
+        Ok(branches)
+    }
+
+    fn status(&self) -> Result<Vec<StatusEntry>, String> {
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false)
+            .renames_head_to_index(true)
+            .renames_index_to_workdir(true);
+        let statuses = self.repo.statuses(Some(&mut options)).map_err(message)?;
+        let [staged, unstaged] = self.line_changes().map_err(message)?;
+        let submodules: HashSet<String> = self
+            .repo
+            .submodules()
+            .map_err(message)?
+            .iter()
+            .map(|submodule| submodule.path().to_string_lossy().into_owned())
+            .collect();
+
+        let mut entries = Vec::new();
+        for entry in statuses.iter() {
+            let (index, worktree) = status_letters(entry.status());
+            let rename = entry.head_to_index().or_else(|| entry.index_to_workdir());
+            let (from, path) = match rename {
+                Some(delta) => (delta.old_file().path(), delta.new_file().path()),
+                None => (None, None),
+            };
+            let path = match path.map(|p| p.to_string_lossy()) {
+                Some(path) => path.into_owned(),
+                None => entry.path().unwrap_or_default().to_string(),
+            };
+            let from = from
+                .map(|from| from.to_string_lossy().into_owned())
+                .filter(|from| *from != path);
+            entries.push(StatusEntry {
+                submodule: submodules.contains(&path),
+                staged: staged.get(&path).copied().unwrap_or_default(),
+                unstaged: unstaged.get(&path).copied().unwrap_or_default(),
+                path,
+                from,
+                index,
+                worktree,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn log(
+        &self,
+        filter: &LogFilter,
+        after: Option<&str>,
+        n: usize,
+    ) -> Result<Vec<CommitInfo>, String> {
+        let order = match self.order()? {
+            Some(order) if n > 0 => order,
+            _ => return Ok(Vec::new()),
+        };
+        let start = match after {
+            Some(id) => {
+                let position = Oid::from_str(id)
+                    .ok()
+                    .and_then(|id| order.positions.get(&id));
+                match position {
+                    Some(position) => position + 1,
+                    None => return Err(format!("{} is no longer reachable from HEAD", id)),
+                }
+            }
+            None => 0,
+        };
+
+        let mut commits = Vec::new();
+        for id in &order.ids[start..] {
+            let commit = self.repo.find_commit(*id).map_err(message)?;
+            if !self.matches(&commit, filter).map_err(message)? {
+                continue;
+            }
+            commits.push(commit_info(&commit));
+            if commits.len() == n {
+                break;
+            }
+        }
+        Ok(commits)
+    }
+
+    fn commit_count(&self) -> Result<usize, String> {
+        Ok(self.order()?.map_or(0, |order| order.ids.len()))
+    }
+
+    fn commit(&self, id: &str) -> Result<CommitDetail, String> {
+        let detail = || -> Result<CommitDetail, git2::Error> {
+            let commit = self.repo.find_commit(git2::Oid::from_str(id)?)?;
+            let parent = commit.parent(0).ok().map(|p| p.tree()).transpose()?;
+            let mut diff =
+                self.repo
+                    .diff_tree_to_tree(parent.as_ref(), Some(&commit.tree()?), None)?;
+            diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+            let files = (0..diff.deltas().len())
+                .map(|i| file_diff(&diff, i))
+                .collect::<Result<_, _>>()?;
+
+            let committer = commit.committer();
+            Ok(CommitDetail {
+                info: commit_info(&commit),
+                message: String::from_utf8_lossy(commit.message_bytes())
+                    .trim_end()
+                    .to_string(),
+                committer: String::from_utf8_lossy(committer.name_bytes()).into_owned(),
+                committer_email: String::from_utf8_lossy(committer.email_bytes()).into_owned(),
+                commit_time: committer.when().seconds(),
+                commit_offset: committer.when().offset_minutes(),
+                files,
+            })
+        };
+        detail().map_err(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::DAY;
+    use crate::git::tests::{at, commit_as, repo};
+    use std::fs;
+
+    #[test]
+    fn reads_branches_status_and_log() {
+        let (root, repo) = repo("backend");
+        let git = Git2Backend::open(root.to_str().unwrap()).unwrap();
+        // The name of the first branch depends on `init.defaultBranch`.
+        let main = git.head().unwrap();
+        assert_eq!(
+            git.log(&LogFilter::default(), None, 10).unwrap(),
+            Vec::new()
+        );
+        assert_eq!(git.commit_count().unwrap(), 0);
+
+        commit_as(
+            &repo,
+            &[("a.txt", "a\n"), ("b.txt", "b\n")],
+            "first",
+            &at("Ada", 0),
+        );
+        let second = commit_as(
+            &repo,
+            &[("a.txt", "a\nb\n"), ("b.txt", "b\n")],
+            "second\n\nWith a body.",
+            &at("Alan", DAY),
+        );
+        let head = repo.find_commit(second).unwrap();
+        repo.branch("feature", &head, false).unwrap();
+
+        let branches = git.branches().unwrap();
+        let names: Vec<(&str, bool)> = branches.iter().map(|b| (b.name.as_str(), b.head)).collect();
+        assert_eq!(names, [("feature", false), (main.as_str(), true)]);
+
+        let log = git.log(&LogFilter::default(), None, 10).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].id, second.to_string());
+        assert_eq!(log[0].parents, [log[1].id.clone()]);
+        assert_eq!(
+            (log[0].author.as_str(), log[0].summary.as_str()),
+            ("Alan", "second")
+        );
+        assert_eq!(log[0].time, DAY);
+        assert!(log[1].parents.is_empty());
+        assert_eq!(
+            git.log(&LogFilter::default(), Some(&log[0].id), 10)
+                .unwrap(),
+            log[1..]
+        );
+        assert_eq!(git.commit_count().unwrap(), 2);
+
+        let detail = git.commit(&second.to_string()).unwrap();
+        assert_eq!(detail.message, "second\n\nWith a body.");
+        assert_eq!(
+            (detail.committer.as_str(), detail.commit_time),
+            ("Alan", DAY)
+        );
+        assert_eq!(detail.files.len(), 1);
+        let file = &detail.files[0];
+        assert_eq!((file.path.as_str(), file.status), ("a.txt", 'M'));
+        assert_eq!(
+            file.changes,
+            LineChanges {
+                added: 1,
+                removed: 0
+            }
+        );
+        let lines: Vec<(char, &str)> = file
+            .lines
+            .iter()
+            .map(|line| (line.origin, line.content.as_str()))
+            .collect();
+        assert_eq!(lines, [('@', "@@ -1 +1,2 @@"), (' ', "a"), ('+', "b")]);
+
+        let first = git.commit(&log[1].id).unwrap();
+        let added: Vec<(char, &str)> = first
+            .files
+            .iter()
+            .map(|file| (file.status, file.path.as_str()))
+            .collect();
+        assert_eq!(added, [('A', "a.txt"), ('A', "b.txt")]);
+
+        fs::write(root.join("a.txt"), "changed\n").unwrap();
+        fs::write(root.join("new.txt"), "new\n").unwrap();
+        fs::rename(root.join("b.txt"), root.join("c.txt")).unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(std::path::Path::new("b.txt")).unwrap();
+        index.add_path(std::path::Path::new("c.txt")).unwrap();
+        index.write().unwrap();
+
+        let mut status = git.status().unwrap();
+        status.sort_by(|a, b| a.path.cmp(&b.path));
+        let codes: Vec<(String, &str, Option<&str>)> = status
+            .iter()
+            .map(|s| (s.code(), s.path.as_str(), s.from.as_deref()))
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                (" M".to_string(), "a.txt", None),
+                ("R ".to_string(), "c.txt", Some("b.txt")),
+                ("??".to_string(), "new.txt", None),
+            ]
+        );
+
+        let changes = |added, removed| LineChanges { added, removed };
+        assert_eq!(status[0].unstaged, changes(1, 2));
+        assert_eq!(
+            status[0].change(StatusGroup::Unstaged),
+            ('M', changes(1, 2))
+        );
+        assert_eq!(status[1].staged, changes(0, 0));
+        assert_eq!(status[2].unstaged, changes(1, 0));
+        let groups: Vec<Vec<StatusGroup>> = status
+            .iter()
+            .map(|entry| {
+                StatusGroup::ALL
+                    .into_iter()
+                    .filter(|group| entry.in_group(*group))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                vec![StatusGroup::Unstaged],
+                vec![StatusGroup::Staged],
+                vec![StatusGroup::Untracked],
+            ]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn filters_the_log() {
+        let (root, repo) = repo("log-filter");
+        let git = Git2Backend::open(root.to_str().unwrap()).unwrap();
+        commit_as(&repo, &[("src/a.rs", "a\n")], "Add a", &at("Ada", 0));
+        commit_as(
+            &repo,
+            &[("src/a.rs", "a\n"), ("README", "hi\n")],
+            "Document it\n\nFixes a typo.",
+            &at("Alan", 2 * DAY),
+        );
+        let last = commit_as(
+            &repo,
+            &[("src/a.rs", "a\nb\n"), ("README", "hi\n")],
+            "Change a",
+            &at("Ada", 4 * DAY),
+        );
+
+        let summaries = |query: &str| -> Vec<String> {
+            let filter = LogFilter::parse(query).unwrap();
+            let log = git.log(&filter, None, 10).unwrap();
+            log.into_iter().map(|commit| commit.summary).collect()
+        };
+        assert_eq!(summaries("author:ada"), ["Change a", "Add a"]);
+        assert_eq!(summaries("path:src/"), ["Change a", "Add a"]);
+        assert_eq!(summaries("TYPO"), ["Document it"]);
+        assert_eq!(summaries(&last.to_string()[..7]), ["Change a"]);
+        assert_eq!(summaries("until:1970-01-03"), ["Document it", "Add a"]);
+        assert_eq!(summaries("since:1970-01-02 path:src"), ["Change a"]);
+        assert!(summaries("author:grace").is_empty());
+
+        let filter = LogFilter::parse("author:ada").unwrap();
+        let first = git.log(&filter, None, 1).unwrap();
+        assert_eq!(first[0].summary, "Change a");
+        let page = git.log(&filter, Some(&first[0].id), 10).unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].summary, "Add a");
+        assert!(git.log(&filter, Some("0123abc"), 10).is_err());
+        assert!(LogFilter::parse("").unwrap().is_empty());
+        assert!(LogFilter::parse("since:yesterday").is_err());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn compares_branches_with_upstream_and_default() {
+        let (root, repo) = repo("branches");
+        let git = Git2Backend::open(root.to_str().unwrap()).unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+        let base = commit_as(&repo, &[("a", "1\n")], "base", &at("Ada", 0));
+        let base = repo.find_commit(base).unwrap();
+        repo.remote("origin", "https://example.com/repo.git")
+            .unwrap();
+        for name in ["feature", "stale"] {
+            repo.reference(
+                &format!("refs/remotes/origin/{}", name),
+                base.id(),
+                false,
+                "",
+            )
+            .unwrap();
+            let mut branch = repo.branch(name, &base, false).unwrap();
+            branch
+                .set_upstream(Some(&format!("origin/{}", name)))
+                .unwrap();
+        }
+        repo.find_reference("refs/remotes/origin/stale")
+            .unwrap()
+            .delete()
+            .unwrap();
+
+        // Two commits on feature that neither main nor its upstream have.
+        repo.set_head("refs/heads/feature").unwrap();
+        commit_as(&repo, &[("a", "2\n")], "one", &at("Alan", DAY));
+        commit_as(&repo, &[("a", "3\n")], "two", &at("Alan", 2 * DAY));
+
+        assert_eq!(git.default_branch(), Some(String::from("main")));
+        let branches = git.branches().unwrap();
+        let summary: Vec<_> = branches
+            .iter()
+            .map(|b| {
+                let upstream = b.upstream.as_deref();
+                (
+                    b.name.as_str(),
+                    b.remote,
+                    upstream,
+                    b.ahead_behind,
+                    b.merged,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "feature",
+                    false,
+                    Some("origin/feature"),
+                    Some((2, 0)),
+                    false
+                ),
+                ("main", false, None, None, true),
+                ("stale", false, Some("origin/stale"), None, true),
+                ("origin/feature", true, None, None, true),
+            ]
+        );
+        assert!(branches[0].head);
+        assert_eq!(branches[0].last.as_ref().unwrap().summary, "two");
+
+        repo.reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/feature",
+            false,
+            "",
+        )
+        .unwrap();
+        assert_eq!(git.default_branch().as_deref(), Some("origin/feature"));
+        assert!(git
+            .branches()
+            .unwrap()
+            .iter()
+            .all(|b| b.name != "origin/HEAD"));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
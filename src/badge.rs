@@ -0,0 +1,284 @@
+use crate::languages;
+use crate::stats::{get_percentages, Scan};
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Look of a badge, following the shields.io styles of the same name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Style {
+    #[default]
+    Flat,
+    FlatSquare,
+    Plastic,
+    ForTheBadge,
+}
+
+impl Style {
+    pub const NAMES: [&'static str; 4] = ["flat", "flat-square", "plastic", "for-the-badge"];
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Style, String> {
+        match name {
+            "flat" => Ok(Style::Flat),
+            "flat-square" => Ok(Style::FlatSquare),
+            "plastic" => Ok(Style::Plastic),
+            "for-the-badge" => Ok(Style::ForTheBadge),
+            _ => Err(format!("Unknown badge style: {}", name)),
+        }
+    }
+}
+
+/// Shields-style color of each color name accepted by `-c`.
+pub fn color(name: &str) -> Option<&'static str> {
+    let hex = match name {
+        "Black" => "#333333",
+        "Red" => "#e05d44",
+        "Green" => "#44cc11",
+        "Yellow" => "#dfb317",
+        "Blue" => "#007ec6",
+        "Magenta" => "#b0389b",
+        "Cyan" => "#1b9aaa",
+        "Gray" => "#9f9f9f",
+        "DarkGray" => "#555555",
+        "LightRed" => "#fe7d37",
+        "LightGreen" => "#97ca00",
+        "LightYellow" => "#e3c84b",
+        "LightBlue" => "#4c9ee0",
+        "LightMagenta" => "#d27bd1",
+        "LightCyan" => "#5fc9d4",
+        "White" => "#dddddd",
+        _ => return None,
+    };
+    Some(hex)
+}
+
+/// Formats a count the way badges usually show it, e.g. `12.3k` or `4.5M`.
+pub fn compact(n: u64) -> String {
+    if n < 1_000 {
+        return n.to_string();
+    }
+    // Rounded before picking the unit, so that 999 950 becomes 1M, not 1000k.
+    let thousands = (n as f64 / 100.0).round() / 10.0;
+    if thousands < 1_000.0 {
+        format!("{:.1}k", thousands).replace(".0k", "k")
+    } else {
+        format!("{:.1}M", n as f64 / 1e6).replace(".0M", "M")
+    }
+}
+
+/// A label on the left, a message on a colored background on the right.
+#[derive(Clone, Debug)]
+pub struct Badge {
+    pub label: String,
+    pub message: String,
+    /// Background of the message, as a CSS color.
+    pub color: String,
+}
+
+impl Badge {
+    fn new(label: &str, message: String, color: &str) -> Badge {
+        Badge {
+            label: label.to_string(),
+            message,
+            color: color.to_string(),
+        }
+    }
+
+    /// Total lines of every file with a known language.
+    pub fn lines(scan: &Scan) -> Badge {
+        let lines: u64 = scan
+            .file_counts
+            .values()
+            .map(|s| s.lines.total() as u64)
+            .sum();
+        Badge::new("lines", compact(lines), "#007ec6")
+    }
+
+    /// Number of files in the project, binaries included.
+    pub fn files(scan: &Scan) -> Badge {
+        Badge::new("files", compact(scan.files.len() as u64), "#007ec6")
+    }
+
+    /// The biggest language of the distribution and its share, in the
+    /// language's own color. `None` if there are no programming or markup files.
+    pub fn language(scan: &Scan) -> Option<Badge> {
+        let percentages = get_percentages(&scan.file_counts);
+        let (name, share) = percentages
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(b.1).then_with(|| b.0.cmp(a.0)))?;
+
+        let color = match languages::color(name) {
+            Some((r, g, b)) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+            None => "#007ec6".to_string(),
+        };
+        Some(Badge::new(
+            "language",
+            format!("{} {:.1}%", name, share),
+            &color,
+        ))
+    }
+
+    pub fn commits(count: usize) -> Badge {
+        Badge::new("commits", compact(count as u64), "#007ec6")
+    }
+
+    /// Renders the badge as a standalone SVG image.
+    pub fn render(&self, style: Style) -> String {
+        let (label, message) = match style {
+            Style::ForTheBadge => (self.label.to_uppercase(), self.message.to_uppercase()),
+            _ => (self.label.clone(), self.message.clone()),
+        };
+        let (height, baseline, padding, radius) = match style {
+            Style::Flat => (20, 14.0, 6.0, 3),
+            Style::FlatSquare => (20, 14.0, 6.0, 0),
+            Style::Plastic => (18, 13.0, 6.0, 4),
+            Style::ForTheBadge => (28, 18.0, 12.0, 0),
+        };
+        let bold = style == Style::ForTheBadge;
+        let left = (text_width(&label, bold) + 2.0 * padding).round();
+        let right = (text_width(&message, bold) + 2.0 * padding).round();
+        let width = left + right;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{}\" role=\"img\" aria-label=\"{}: {}\">\n",
+            width,
+            height,
+            escape(&self.label),
+            escape(&self.message)
+        );
+        let _ = writeln!(
+            svg,
+            "<title>{}: {}</title>",
+            escape(&self.label),
+            escape(&self.message)
+        );
+
+        let gradient = match style {
+            Style::Flat => Some("<stop offset=\"0\" stop-color=\"#bbb\" stop-opacity=\".1\"/><stop offset=\"1\" stop-opacity=\".1\"/>"),
+            Style::Plastic => Some("<stop offset=\"0\" stop-color=\"#fff\" stop-opacity=\".7\"/><stop offset=\".1\" stop-color=\"#aaa\" stop-opacity=\".1\"/><stop offset=\".9\" stop-opacity=\".3\"/><stop offset=\"1\" stop-opacity=\".5\"/>"),
+            _ => None,
+        };
+        if let Some(stops) = gradient {
+            let _ = writeln!(
+                svg,
+                "<linearGradient id=\"s\" x2=\"0\" y2=\"100%\">{}</linearGradient>",
+                stops
+            );
+        }
+        let _ = writeln!(
+            svg,
+            "<clipPath id=\"r\"><rect width=\"{:.0}\" height=\"{}\" rx=\"{}\" fill=\"#fff\"/></clipPath>",
+            width, height, radius
+        );
+        let _ = writeln!(
+            svg,
+            "<g clip-path=\"url(#r)\"><rect width=\"{:.0}\" height=\"{}\" fill=\"#555\"/><rect x=\"{:.0}\" width=\"{:.0}\" height=\"{}\" fill=\"{}\"/>{}</g>",
+            left,
+            height,
+            left,
+            right,
+            height,
+            escape(&self.color),
+            if gradient.is_some() {
+                format!("<rect width=\"{:.0}\" height=\"{}\" fill=\"url(#s)\"/>", width, height)
+            } else {
+                String::new()
+            }
+        );
+
+        let font = if bold {
+            "font-size=\"10\" font-weight=\"bold\" letter-spacing=\"1\""
+        } else {
+            "font-size=\"11\""
+        };
+        let _ = writeln!(
+            svg,
+            "<g fill=\"#fff\" text-anchor=\"middle\" font-family=\"Verdana,Geneva,DejaVu Sans,sans-serif\" {}>",
+            font
+        );
+        for (text, x) in [(&label, left / 2.0), (&message, left + right / 2.0)] {
+            if style != Style::ForTheBadge {
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{:.1}\" y=\"{:.0}\" fill=\"#010101\" fill-opacity=\".3\">{}</text>",
+                    x,
+                    baseline + 1.0,
+                    escape(text)
+                );
+            }
+            let _ = writeln!(
+                svg,
+                "<text x=\"{:.1}\" y=\"{:.0}\">{}</text>",
+                x,
+                baseline,
+                escape(text)
+            );
+        }
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+}
+
+/// Approximate width in pixels of `text` set in 11px Verdana, or 10px bold
+/// Verdana with letter spacing for the `for-the-badge` style.
+fn text_width(text: &str, bold: bool) -> f64 {
+    let width: f64 = text
+        .chars()
+        .map(|c| match c {
+            'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '\'' | '!' | '|' => 3.5,
+            'f' | 'r' | 't' | 'I' | ' ' | '(' | ')' | '[' | ']' | '-' => 5.0,
+            'm' | 'w' | 'M' | 'W' | '%' => 10.5,
+            'A'..='Z' => 7.5,
+            _ => 6.8,
+        })
+        .sum();
+    if bold {
+        width * 0.95 + text.chars().count() as f64
+    } else {
+        width
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_compact_counts() {
+        assert_eq!(compact(999), "999");
+        assert_eq!(compact(1_000), "1k");
+        assert_eq!(compact(12_345), "12.3k");
+        assert_eq!(compact(4_500_000), "4.5M");
+        assert_eq!(compact(999_949), "999.9k");
+        assert_eq!(compact(999_950), "1M");
+    }
+
+    #[test]
+    fn renders_styles() {
+        let badge = Badge::new("lines", "12.3k".to_string(), "#007ec6");
+
+        let flat = badge.render(Style::Flat);
+        assert!(flat.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(flat.contains("aria-label=\"lines: 12.3k\""));
+        assert!(flat.contains("rx=\"3\""));
+        assert!(flat.contains("url(#s)"));
+
+        let square = badge.render(Style::FlatSquare);
+        assert!(square.contains("rx=\"0\""));
+        assert!(!square.contains("url(#s)"));
+
+        let big = badge.render(Style::ForTheBadge);
+        assert!(big.contains("height=\"28\""));
+        assert!(big.contains(">LINES</text>"));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::backend::Branch;
+use crate::{dates, export};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::str::FromStr;
+
+/// Order of the branch overview. Local branches come before remote-tracking
+/// ones by name, or all of them by their last commit, newest first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BranchSort {
+    #[default]
+    Name,
+    Recent,
+}
+
+impl BranchSort {
+    pub const NAMES: [&'static str; 2] = ["name", "recent"];
+
+    /// The next order, for cycling through them in the TUI.
+    pub fn next(self) -> BranchSort {
+        match self {
+            BranchSort::Name => BranchSort::Recent,
+            BranchSort::Recent => BranchSort::Name,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BranchSort::Name => "name",
+            BranchSort::Recent => "recent",
+        }
+    }
+}
+
+impl FromStr for BranchSort {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<BranchSort, String> {
+        match name {
+            "name" => Ok(BranchSort::Name),
+            "recent" => Ok(BranchSort::Recent),
+            _ => Err(format!("Unknown sort order: {}", name)),
+        }
+    }
+}
+
+/// Where a branch stands against its upstream and the default branch.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BranchReport {
+    pub name: String,
+    pub remote: bool,
+    /// Whether the branch is checked out.
+    pub head: bool,
+    /// Whether this is the branch the others are compared against.
+    pub default: bool,
+    pub upstream: Option<String>,
+    /// Whether the upstream was deleted, like `[gone]` in `git branch -vv`.
+    pub gone: bool,
+    /// Commits not in the upstream and commits of the upstream not in the
+    /// branch, `None` without an upstream.
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+    /// Date of the last commit in its author's time zone, `YYYY-MM-DD`.
+    pub date: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    /// Whether the last commit is part of the default branch, so the branch
+    /// can be deleted without losing work.
+    pub merged: bool,
+}
+
+/// The branches in the given order, compared against the `default` branch.
+pub fn report(branches: &[Branch], default: Option<&str>, sort: BranchSort) -> Vec<BranchReport> {
+    let mut branches: Vec<&Branch> = branches.iter().collect();
+    if sort == BranchSort::Recent {
+        // Stable, so branches with the same commit stay in name order.
+        branches.sort_by_key(|branch| Reverse(branch.last.as_ref().map(|commit| commit.time)));
+    }
+
+    branches
+        .into_iter()
+        .map(|branch| {
+            let last = branch.last.as_ref();
+            BranchReport {
+                name: branch.name.clone(),
+                remote: branch.remote,
+                head: branch.head,
+                default: Some(branch.name.as_str()) == default,
+                upstream: branch.upstream.clone(),
+                gone: branch.upstream.is_some() && branch.ahead_behind.is_none(),
+                ahead: branch.ahead_behind.map(|(ahead, _)| ahead),
+                behind: branch.ahead_behind.map(|(_, behind)| behind),
+                date: last.map(|commit| dates::format(commit.time + commit.offset as i64 * 60)),
+                author: last.map(|commit| commit.author.clone()),
+                subject: last.map(|commit| commit.summary.clone()),
+                merged: branch.merged,
+            }
+        })
+        .collect()
+}
+
+impl BranchReport {
+    /// The upstream with how far the branch is ahead and behind it, like
+    /// `origin/main +1 -2`.
+    pub fn tracking(&self) -> String {
+        match (&self.upstream, self.ahead, self.behind) {
+            (None, _, _) => String::new(),
+            (Some(upstream), _, _) if self.gone => format!("{} (gone)", upstream),
+            (Some(upstream), Some(0), Some(0)) => upstream.clone(),
+            (Some(upstream), ahead, behind) => format!(
+                "{} +{} -{}",
+                upstream,
+                ahead.unwrap_or(0),
+                behind.unwrap_or(0)
+            ),
+        }
+    }
+
+    /// `default` for the default branch, and otherwise whether the branch is
+    /// merged into it.
+    pub fn merge_state(&self) -> &'static str {
+        match (self.default, self.merged) {
+            (true, _) => "default",
+            (false, true) => "merged",
+            (false, false) => "",
+        }
+    }
+}
+
+fn rows(branches: &[BranchReport]) -> Vec<Vec<String>> {
+    let header = [
+        "Branch", "Upstream", "Ahead", "Behind", "Date", "Author", "Merged", "Subject",
+    ];
+    let mut rows = vec![header.iter().map(|h| h.to_string()).collect()];
+    let count = |count: Option<usize>| count.map_or(String::new(), |count| count.to_string());
+    for b in branches {
+        rows.push(vec![
+            b.name.clone(),
+            match b.gone {
+                true => format!("{} (gone)", b.upstream.clone().unwrap_or_default()),
+                false => b.upstream.clone().unwrap_or_default(),
+            },
+            count(b.ahead),
+            count(b.behind),
+            b.date.clone().unwrap_or_default(),
+            b.author.clone().unwrap_or_default(),
+            b.merge_state().to_string(),
+            b.subject.clone().unwrap_or_default(),
+        ]);
+    }
+    rows
+}
+
+/// Renders the branches as a plain text table.
+pub fn text(branches: &[BranchReport]) -> String {
+    if branches.is_empty() {
+        return "No branches found.\n".to_string();
+    }
+    export::text_rows(&rows(branches))
+}
+
+pub fn markdown(branches: &[BranchReport]) -> String {
+    export::markdown_rows(&rows(branches))
+}
+
+pub fn csv(branches: &[BranchReport]) -> String {
+    export::csv_rows(&rows(branches))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::CommitInfo;
+
+    fn branch(name: &str, remote: bool, time: i64) -> Branch {
+        Branch {
+            name: name.to_string(),
+            head: false,
+            remote,
+            upstream: None,
+            ahead_behind: None,
+            last: Some(CommitInfo {
+                id: String::new(),
+                parents: Vec::new(),
+                author: String::from("Ada"),
+                email: String::from("ada@example.com"),
+                time,
+                offset: -60,
+                summary: format!("Work on {}", name),
+            }),
+            merged: false,
+        }
+    }
+
+    #[test]
+    fn reports_branches_by_recency() {
+        let mut feature = branch("feature", false, 3 * dates::DAY);
+        feature.upstream = Some(String::from("origin/feature"));
+        feature.ahead_behind = Some((2, 0));
+        let mut stale = branch("stale", false, dates::DAY);
+        stale.upstream = Some(String::from("origin/stale"));
+        stale.merged = true;
+        let branches = [
+            feature,
+            branch("main", false, 2 * dates::DAY),
+            stale,
+            branch("origin/main", true, 2 * dates::DAY),
+        ];
+
+        let by_name = report(&branches, Some("main"), BranchSort::Name);
+        let names: Vec<&str> = by_name.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["feature", "main", "stale", "origin/main"]);
+
+        let recent = report(&branches, Some("main"), BranchSort::Recent);
+        let names: Vec<&str> = recent.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["feature", "main", "origin/main", "stale"]);
+
+        assert_eq!(recent[0].tracking(), "origin/feature +2 -0");
+        assert_eq!(recent[1].merge_state(), "default");
+        assert_eq!(recent[3].tracking(), "origin/stale (gone)");
+        assert_eq!(recent[3].merge_state(), "merged");
+        // Midnight UTC is still the day before an hour west of Greenwich.
+        assert_eq!(recent[3].date.as_deref(), Some("1970-01-01"));
+    }
+}
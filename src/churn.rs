@@ -0,0 +1,412 @@
+use crate::stats::Scan;
+use crate::{export, git};
+use git2::{Delta, DiffFindOptions, DiffOptions, Patch, Repository, Sort};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Version of the per-commit cache, bumped when the way lines are counted changes.
+const CACHE_VERSION: u32 = 2;
+
+/// The lines one non-merge commit changed in one file.
+#[derive(Clone, Debug)]
+pub struct Change {
+    /// Path relative to the scanned directory.
+    pub path: PathBuf,
+    /// Author time in seconds since the Unix epoch.
+    pub time: i64,
+    pub added: u32,
+    pub removed: u32,
+}
+
+/// A file changed by a commit: its path relative to the repository, the path
+/// it had before a rename, and the added and removed lines.
+type FileChange = (String, Option<String>, u32, u32);
+
+/// Changed files of already diffed commits by commit id, per scanned
+/// directory, so that later runs only diff new commits.
+type Cache = git::Cache<HashMap<String, HashMap<String, Vec<FileChange>>>>;
+
+/// Lines added and removed per file under `prefix` by a commit, compared to
+/// its first parent. Renamed files also carry their old path.
+fn diff(
+    repo: &Repository,
+    commit: &git2::Commit,
+    prefix: &Path,
+) -> Result<Vec<FileChange>, git2::Error> {
+    let parent = commit.parent(0).ok().map(|p| p.tree()).transpose()?;
+    let tree = commit.tree()?;
+    let mut options = DiffOptions::new();
+    options.ignore_submodules(true);
+    if !prefix.as_os_str().is_empty() {
+        options.pathspec(prefix);
+    }
+    let mut diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&tree), Some(&mut options))?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+    let mut files = Vec::new();
+    for (i, delta) in diff.deltas().enumerate() {
+        let path = match delta.new_file().path().or_else(|| delta.old_file().path()) {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None => continue,
+        };
+        let old = match delta.status() {
+            Delta::Renamed => delta
+                .old_file()
+                .path()
+                .map(|path| path.to_string_lossy().into_owned()),
+            _ => None,
+        };
+        // Binary files have no patch and count no lines.
+        let (added, removed) = match Patch::from_diff(&diff, i)? {
+            Some(patch) => {
+                let (_, added, removed) = patch.line_stats()?;
+                (added as u32, removed as u32)
+            }
+            None => (0, 0),
+        };
+        files.push((path, old, added, removed));
+    }
+    Ok(files)
+}
+
+/// Every file changed by the non-merge commits reachable from HEAD in the
+/// repository holding `path`, for the files under `path`, newest first.
+/// Changes made before a file was renamed count for its current path.
+pub fn changes(path: &str) -> Result<Vec<Change>, String> {
+    let repo = Repository::discover(path).map_err(|e| e.message().to_string())?;
+    let prefix = git::prefix(&repo, path)?;
+    let mut cache = Cache::load(&repo, "churn.json", CACHE_VERSION);
+    let key = prefix.to_string_lossy().into_owned();
+    let cached = cache.entries.entry(key).or_default();
+
+    let mut walk = repo.revwalk().map_err(|e| e.message().to_string())?;
+    // Topological, so a rename is always seen before the older changes.
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)
+        .map_err(|e| e.message().to_string())?;
+    if walk.push_head().is_err() {
+        // No commits yet.
+        return Ok(Vec::new());
+    }
+
+    // Current path of renamed files by their old path.
+    let mut renames: HashMap<String, String> = HashMap::new();
+    let mut changes = Vec::new();
+    for id in walk {
+        let commit = id
+            .and_then(|id| repo.find_commit(id))
+            .map_err(|e| e.message().to_string())?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+
+        let id = commit.id().to_string();
+        let files = match cached.get(&id) {
+            Some(files) => files,
+            None => {
+                let files = diff(&repo, &commit, &prefix).map_err(|e| e.message().to_string())?;
+                cached.entry(id).or_insert(files)
+            }
+        };
+        let time = commit.author().when().seconds();
+        for (file, old, added, removed) in files {
+            let current = renames.get(file).cloned().unwrap_or_else(|| file.clone());
+            if let Some(old) = old {
+                renames.insert(old.clone(), current.clone());
+            }
+            if let Ok(path) = Path::new(&current).strip_prefix(&prefix) {
+                changes.push(Change {
+                    path: path.to_path_buf(),
+                    time,
+                    added: *added,
+                    removed: *removed,
+                });
+            }
+        }
+    }
+
+    cache.save();
+    Ok(changes)
+}
+
+/// Column the hotspot table is sorted by. Paths sort alphabetically,
+/// everything else largest first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HotspotSort {
+    Path,
+    Commits,
+    Churn,
+    Code,
+    #[default]
+    Score,
+}
+
+impl HotspotSort {
+    pub const NAMES: [&'static str; 5] = ["path", "commits", "churn", "code", "score"];
+
+    /// The next column, for cycling through them in the TUI.
+    pub fn next(self) -> HotspotSort {
+        let i = HotspotSort::NAMES
+            .iter()
+            .position(|name| *name == self.name())
+            .unwrap_or(0);
+        HotspotSort::NAMES[(i + 1) % HotspotSort::NAMES.len()]
+            .parse()
+            .unwrap_or_default()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HotspotSort::Path => "path",
+            HotspotSort::Commits => "commits",
+            HotspotSort::Churn => "churn",
+            HotspotSort::Code => "code",
+            HotspotSort::Score => "score",
+        }
+    }
+}
+
+impl FromStr for HotspotSort {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<HotspotSort, String> {
+        match name {
+            "path" => Ok(HotspotSort::Path),
+            "commits" => Ok(HotspotSort::Commits),
+            "churn" => Ok(HotspotSort::Churn),
+            "code" => Ok(HotspotSort::Code),
+            "score" => Ok(HotspotSort::Score),
+            _ => Err(format!("Unknown sort column: {}", name)),
+        }
+    }
+}
+
+/// How often a file of the project changed over a time range.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Hotspot {
+    pub path: String,
+    pub language: Option<String>,
+    pub commits: u32,
+    pub added: u32,
+    pub removed: u32,
+    /// Lines of code in the file now.
+    pub code: u32,
+    /// Commits plus churn, times lines of code. Highest for big files that
+    /// change often or a lot.
+    pub score: u64,
+}
+
+impl Hotspot {
+    /// Lines added plus lines removed.
+    pub fn churn(&self) -> u32 {
+        self.added + self.removed
+    }
+}
+
+/// Ranks the text files of `scan` by their changes made from `since`
+/// (inclusive) until `until` (exclusive), in seconds since the epoch. Files
+/// without changes in that range are left out.
+pub fn hotspots(
+    changes: &[Change],
+    scan: &Scan,
+    since: Option<i64>,
+    until: Option<i64>,
+    sort: HotspotSort,
+) -> Vec<Hotspot> {
+    let mut totals: HashMap<&Path, (u32, u32, u32)> = HashMap::new();
+    let in_range = |c: &&Change| {
+        since.is_none_or(|since| c.time >= since) && until.is_none_or(|until| c.time < until)
+    };
+    for change in changes.iter().filter(in_range) {
+        let (commits, added, removed) = totals.entry(&change.path).or_default();
+        *commits += 1;
+        *added += change.added;
+        *removed += change.removed;
+    }
+
+    let mut hotspots: Vec<Hotspot> = scan
+        .files
+        .iter()
+        .filter(|file| !file.binary)
+        .filter_map(|file| {
+            let &(commits, added, removed) = totals.get(file.path.as_path())?;
+            Some(Hotspot {
+                path: file.path.to_string_lossy().into_owned(),
+                language: file.language.clone(),
+                commits,
+                added,
+                removed,
+                code: file.lines.code,
+                score: (u64::from(commits) + u64::from(added) + u64::from(removed))
+                    * u64::from(file.lines.code),
+            })
+        })
+        .collect();
+
+    hotspots.sort_by(|a, b| {
+        let by = match sort {
+            HotspotSort::Path => a.path.cmp(&b.path),
+            HotspotSort::Commits => b.commits.cmp(&a.commits),
+            HotspotSort::Churn => b.churn().cmp(&a.churn()),
+            HotspotSort::Code => b.code.cmp(&a.code),
+            HotspotSort::Score => b.score.cmp(&a.score),
+        };
+        by.then_with(|| a.path.cmp(&b.path))
+    });
+    hotspots
+}
+
+fn rows(hotspots: &[Hotspot]) -> Vec<Vec<String>> {
+    let header = [
+        "File", "Language", "Commits", "Added", "Removed", "Code", "Score",
+    ];
+    let mut rows = vec![header.iter().map(|h| h.to_string()).collect()];
+    for h in hotspots {
+        rows.push(vec![
+            h.path.clone(),
+            h.language.clone().unwrap_or_default(),
+            h.commits.to_string(),
+            h.added.to_string(),
+            h.removed.to_string(),
+            h.code.to_string(),
+            h.score.to_string(),
+        ]);
+    }
+    rows
+}
+
+/// Renders the hotspots as a plain text table.
+pub fn text(hotspots: &[Hotspot]) -> String {
+    if hotspots.is_empty() {
+        return "No changed files found.\n".to_string();
+    }
+    export::text_rows(&rows(hotspots))
+}
+
+pub fn markdown(hotspots: &[Hotspot]) -> String {
+    export::markdown_rows(&rows(hotspots))
+}
+
+pub fn csv(hotspots: &[Hotspot]) -> String {
+    export::csv_rows(&rows(hotspots))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::DAY;
+    use crate::git::tests::{at, commit_as, repo};
+    use crate::languages::Languages;
+    use crate::stats::FileStat;
+    use std::fs;
+
+    #[test]
+    fn ranks_big_files_that_change_often() {
+        let (root, repo) = repo("churn");
+        let big = "fn main() {\n    let a = 1;\n    let b = 2;\n}\n";
+        commit_as(
+            &repo,
+            &[("src/big.rs", big), ("src/small.rs", "fn f() {}\n")],
+            "first",
+            &at("Ada", 0),
+        );
+        commit_as(
+            &repo,
+            &[
+                ("src/big.rs", &big.replace('2', "3")),
+                ("src/small.rs", "fn f() {}\n"),
+            ],
+            "second",
+            &at("Ada", DAY),
+        );
+        commit_as(
+            &repo,
+            &[
+                ("src/big.rs", &big.replace('2', "3")),
+                ("src/small.rs", "fn g() {}\n"),
+            ],
+            "third",
+            &at("Ada", 2 * DAY),
+        );
+
+        // Only the files under the scanned directory count, relative to it.
+        let src = root.join("src");
+        let changes = changes(src.to_str().unwrap()).unwrap();
+        assert_eq!(changes.len(), 4);
+        assert!(changes
+            .iter()
+            .all(|c| c.path.parent() == Some(Path::new(""))));
+
+        let languages = Languages::builtin();
+        let files = ["big.rs", "small.rs"]
+            .iter()
+            .map(|name| {
+                let contents = fs::read(src.join(name)).unwrap();
+                FileStat::from_contents(Path::new(name), contents, &languages)
+            })
+            .collect();
+        let scan = Scan::new(files, Vec::new());
+
+        let ranked = hotspots(&changes, &scan, None, None, HotspotSort::Score);
+        assert_eq!(ranked[0].path, "big.rs");
+        assert_eq!(
+            (
+                ranked[0].commits,
+                ranked[0].added,
+                ranked[0].removed,
+                ranked[0].code
+            ),
+            (2, 5, 1, 4)
+        );
+        // (2 commits + 6 changed lines) * 4 lines and (2 + 3) * 1.
+        assert_eq!(ranked[0].score, 32);
+        assert_eq!(ranked[1].score, 5);
+
+        let recent = hotspots(&changes, &scan, Some(2 * DAY), None, HotspotSort::Churn);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(
+            (recent[0].path.as_str(), recent[0].churn()),
+            ("small.rs", 2)
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn follows_renames() {
+        let (root, repo) = repo("churn-renames");
+        let code = "fn main() {\n    let a = 1;\n}\n";
+        commit_as(&repo, &[("old.rs", code)], "first", &at("Ada", 0));
+        commit_as(
+            &repo,
+            &[("old.rs", &code.replace('1', "2"))],
+            "second",
+            &at("Ada", DAY),
+        );
+        commit_as(
+            &repo,
+            &[("new.rs", &code.replace('1', "2"))],
+            "rename",
+            &at("Ada", 2 * DAY),
+        );
+
+        // The rename itself changes no lines, and the older changes count
+        // for the new path.
+        let changes = changes(root.to_str().unwrap()).unwrap();
+        let counts: Vec<(&Path, u32, u32)> = changes
+            .iter()
+            .map(|c| (c.path.as_path(), c.added, c.removed))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                (Path::new("new.rs"), 0, 0),
+                (Path::new("new.rs"), 1, 1),
+                (Path::new("new.rs"), 3, 0)
+            ]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+//! Calendar dates of git timestamps, without pulling in a date library.
+
+pub const DAY: i64 = 86_400;
+
+/// Formats seconds since the Unix epoch as a `YYYY-MM-DD` date.
+pub fn format(seconds: i64) -> String {
+    let (year, month, day) = civil(seconds.div_euclid(DAY));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Formats a git time, seconds since the Unix epoch and an offset in
+/// minutes, in RFC 3339 format, like `2024-01-01T13:00:00+01:00`.
+pub fn rfc3339(seconds: i64, offset: i32) -> String {
+    let local = seconds + i64::from(offset) * 60;
+    let time = local.rem_euclid(DAY);
+    format!(
+        "{}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        format(local),
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+/// Month of the year of seconds since the Unix epoch, from 1 to 12.
+pub fn month(seconds: i64) -> u32 {
+    civil(seconds.div_euclid(DAY)).1 as u32
+}
+
+/// Parses a `YYYY-MM-DD` date into seconds since the Unix epoch, at midnight UTC.
+pub fn parse(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    // Reject days past the end of the month, like 2023-02-30.
+    (civil(days) == (year, month, day)).then_some(days * DAY)
+}
+
+/// Year, month and day of a day count since 1970-01-01, after Howard
+/// Hinnant's `civil_from_days`.
+fn civil(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Inverse of `civil`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Monday 2024-01-01 12:00 UTC.
+    const MONDAY: i64 = 1_704_110_400;
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(format(0), "1970-01-01");
+        assert_eq!(format(MONDAY), "2024-01-01");
+        assert_eq!(format(MONDAY + 59 * DAY), "2024-02-29");
+        assert_eq!(format(-DAY), "1969-12-31");
+        assert_eq!(month(MONDAY + 59 * DAY), 2);
+        assert_eq!(rfc3339(MONDAY, 60), "2024-01-01T13:00:00+01:00");
+        assert_eq!(rfc3339(MONDAY, -330), "2024-01-01T06:30:00-05:30");
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(parse("1970-01-01"), Some(0));
+        assert_eq!(parse("2024-01-01"), Some(MONDAY - DAY / 2));
+        assert_eq!(
+            parse("2024-02-29").map(format).as_deref(),
+            Some("2024-02-29")
+        );
+        assert_eq!(parse("2023-02-29"), None);
+        assert_eq!(parse("2024-13-01"), None);
+        assert_eq!(parse("yesterday"), None);
+    }
+}
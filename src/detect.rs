@@ -0,0 +1,227 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// How much of an extensionless file is inspected for a shebang or modeline.
+pub const HEAD_SIZE: u64 = 1024;
+
+/// How much of a file is inspected when deciding whether it is binary.
+pub const SNIFF_SIZE: u64 = 8192;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// Reads up to `limit` bytes from the start of `file`.
+pub fn read_bytes(file: &Path, limit: u64) -> io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    File::open(file)?.take(limit).read_to_end(&mut head)?;
+    Ok(head)
+}
+
+/// Reads the first few hundred bytes of `file`, lossily decoded.
+pub fn read_head(file: &Path) -> io::Result<String> {
+    let head = read_bytes(file, HEAD_SIZE)?;
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Guesses whether `bytes` hold binary data: a NUL byte or more than 10% of
+/// control characters other than whitespace in the first few kilobytes.
+/// UTF-16 text, which is full of NULs, is recognized by its byte order mark.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    if utf16_bom(bytes).is_some() {
+        return false;
+    }
+
+    let head = &bytes[..bytes.len().min(SNIFF_SIZE as usize)];
+    if head.contains(&0) {
+        return true;
+    }
+    let control = head
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+    control * 10 > head.len()
+}
+
+/// Decodes text as UTF-16 if it starts with a UTF-16 byte order mark, otherwise
+/// as UTF-8, falling back to Latin-1 for legacy files that aren't valid UTF-8.
+pub fn decode(bytes: Vec<u8>) -> String {
+    if let Some(big_endian) = utf16_bom(&bytes) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|pair| match big_endian {
+                true => u16::from_be_bytes([pair[0], pair[1]]),
+                false => u16::from_le_bytes([pair[0], pair[1]]),
+            })
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+
+    let bytes = match bytes.strip_prefix(UTF8_BOM) {
+        Some(rest) => rest.to_vec(),
+        None => bytes,
+    };
+    match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => e.into_bytes().iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// Returns whether `bytes` start with a big-endian or little-endian UTF-16 BOM.
+fn utf16_bom(bytes: &[u8]) -> Option<bool> {
+    match bytes {
+        [0xFE, 0xFF, ..] => Some(true),
+        [0xFF, 0xFE, ..] => Some(false),
+        _ => None,
+    }
+}
+
+/// Returns the interpreter named by a `#!` line, e.g. `python3` for
+/// `#!/usr/bin/env python3` or `bash` for `#!/bin/bash -e`.
+pub fn shebang(head: &str) -> Option<String> {
+    let line = head.lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let mut program = basename(words.next()?);
+
+    if program == "env" {
+        // Skip env's own flags (`-S`, `-i`) and VAR=value assignments.
+        program = basename(words.find(|w| !w.starts_with('-') && !w.contains('='))?);
+    }
+
+    Some(program.to_string())
+}
+
+/// Returns the file type named by a Vim (`vim: set ft=python:`) or Emacs
+/// (`-*- mode: ruby -*-`) modeline in the first lines of the file.
+pub fn modeline(head: &str) -> Option<String> {
+    head.lines()
+        .take(5)
+        .find_map(|line| emacs_mode(line).or_else(|| vim_filetype(line)))
+        .map(|mode| mode.to_lowercase())
+}
+
+/// Strips version suffixes from an interpreter name, `python3.11` -> `python`.
+pub fn strip_version(interpreter: &str) -> &str {
+    interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.' || c == '-')
+}
+
+fn basename(program: &str) -> &str {
+    program.rsplit(['/', '\\']).next().unwrap_or(program)
+}
+
+fn emacs_mode(line: &str) -> Option<&str> {
+    let start = line.find("-*-")? + 3;
+    let end = start + line[start..].find("-*-")?;
+    let vars = line[start..end].trim();
+
+    if !vars.contains(':') {
+        return Some(vars).filter(|v| !v.is_empty());
+    }
+    vars.split(';').find_map(|var| {
+        let (key, value) = var.split_once(':')?;
+        (key.trim().eq_ignore_ascii_case("mode")).then(|| value.trim())
+    })
+}
+
+fn vim_filetype(line: &str) -> Option<&str> {
+    let start = ["vim:", "vi:", "ex:"]
+        .iter()
+        .filter_map(|marker| line.find(marker).map(|i| i + marker.len()))
+        .min()?;
+
+    line[start..]
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .find_map(|option| {
+            let (key, value) = option.split_once('=')?;
+            matches!(key, "ft" | "filetype" | "syn" | "syntax").then_some(value)
+        })
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::Languages;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/detect")
+            .join(name)
+    }
+
+    fn detect(name: &str) -> Option<String> {
+        let languages = Languages::builtin();
+        languages.detect(&fixture(name)).map(|l| l.name.clone())
+    }
+
+    #[test]
+    fn parses_shebangs() {
+        assert_eq!(shebang("#!/bin/bash -e\n").as_deref(), Some("bash"));
+        assert_eq!(
+            shebang("#!/usr/bin/env python3\n").as_deref(),
+            Some("python3")
+        );
+        assert_eq!(
+            shebang("#!/usr/bin/env -S LANG=C node --harmony\n").as_deref(),
+            Some("node")
+        );
+        assert_eq!(shebang("#! /usr/bin/perl -w\n").as_deref(), Some("perl"));
+        assert_eq!(shebang("echo hi\n#!/bin/sh\n"), None);
+        assert_eq!(strip_version("python3.11"), "python");
+    }
+
+    #[test]
+    fn parses_modelines() {
+        assert_eq!(
+            modeline("# vim: set ft=ruby ts=2:\n").as_deref(),
+            Some("ruby")
+        );
+        assert_eq!(
+            modeline("// vim:filetype=javascript\n").as_deref(),
+            Some("javascript")
+        );
+        assert_eq!(modeline(";; -*- Lisp -*-\n").as_deref(), Some("lisp"));
+        assert_eq!(
+            modeline("# -*- mode: python; coding: utf-8 -*-\n").as_deref(),
+            Some("python")
+        );
+        assert_eq!(modeline("nothing to see here\n"), None);
+    }
+
+    #[test]
+    fn detects_shebang_scripts() {
+        assert_eq!(detect("deploy").as_deref(), Some("Python"));
+        assert_eq!(detect("bootstrap").as_deref(), Some("Shell"));
+        assert_eq!(detect("serve").as_deref(), Some("JavaScript"));
+    }
+
+    #[test]
+    fn detects_modelines() {
+        assert_eq!(detect("provision").as_deref(), Some("Ruby"));
+        assert_eq!(detect("setup-env").as_deref(), Some("Shell"));
+    }
+
+    #[test]
+    fn sniffs_binaries() {
+        assert!(is_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(is_binary(&[0x01, 0x02, 0x03, 0x04, b'a']));
+        assert!(!is_binary(b"fn main() {}\n"));
+        assert!(!is_binary(b""));
+        assert!(!is_binary(&[0xFF, 0xFE, b'h', 0, b'i', 0]));
+    }
+
+    #[test]
+    fn decodes_legacy_encodings() {
+        assert_eq!(decode(vec![0xFF, 0xFE, b'h', 0, b'i', 0]), "hi");
+        assert_eq!(decode(vec![0xFE, 0xFF, 0, b'h', 0, b'i']), "hi");
+        assert_eq!(decode(vec![0xEF, 0xBB, 0xBF, b'o', b'k']), "ok");
+        assert_eq!(decode(b"caf\xe9\n".to_vec()), "caf\u{e9}\n");
+    }
+
+    #[test]
+    fn falls_back_to_filenames() {
+        assert_eq!(detect("Makefile").as_deref(), Some("Makefile"));
+        assert_eq!(detect("Dockerfile").as_deref(), Some("Dockerfile"));
+        assert_eq!(detect("notes"), None);
+    }
+}
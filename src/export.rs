@@ -0,0 +1,323 @@
+use crate::report::{LanguageReport, Report, Totals};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// Column the exported language table is sorted by. Names sort alphabetically,
+/// everything else largest first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortBy {
+    Language,
+    Files,
+    Code,
+    Comments,
+    Blanks,
+    Lines,
+    #[default]
+    Bytes,
+    Percentage,
+}
+
+impl SortBy {
+    pub const NAMES: [&'static str; 8] = [
+        "language",
+        "files",
+        "code",
+        "comments",
+        "blanks",
+        "lines",
+        "bytes",
+        "percentage",
+    ];
+
+    fn compare(self, a: &LanguageReport, b: &LanguageReport) -> Ordering {
+        let by = match self {
+            SortBy::Language => return a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortBy::Files => b.files.cmp(&a.files),
+            SortBy::Code => b.lines.code.cmp(&a.lines.code),
+            SortBy::Comments => b.lines.comments.cmp(&a.lines.comments),
+            SortBy::Blanks => b.lines.blanks.cmp(&a.lines.blanks),
+            SortBy::Lines => b.lines.total().cmp(&a.lines.total()),
+            SortBy::Bytes => b.bytes.cmp(&a.bytes),
+            // Languages without a percentage go last.
+            SortBy::Percentage => match (a.percentage, b.percentage) {
+                (Some(a), Some(b)) => b.total_cmp(&a),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+        };
+        by.then_with(|| a.name.cmp(&b.name))
+    }
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<SortBy, String> {
+        match name {
+            "language" => Ok(SortBy::Language),
+            "files" => Ok(SortBy::Files),
+            "code" => Ok(SortBy::Code),
+            "comments" => Ok(SortBy::Comments),
+            "blanks" => Ok(SortBy::Blanks),
+            "lines" => Ok(SortBy::Lines),
+            "bytes" => Ok(SortBy::Bytes),
+            "percentage" => Ok(SortBy::Percentage),
+            _ => Err(format!("Unknown sort column: {}", name)),
+        }
+    }
+}
+
+/// How the File Stats table is exported.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TableOptions {
+    pub sort: SortBy,
+    /// Whether to end the table with a row of column totals.
+    pub totals: bool,
+}
+
+const HEADER: [&str; 9] = [
+    "Language",
+    "Files",
+    "Code",
+    "Comments",
+    "Blanks",
+    "Tests",
+    "Minified",
+    "Bytes",
+    "Percentage",
+];
+
+/// The cells of the language table, header first.
+pub(crate) fn table(report: &Report, options: TableOptions) -> Vec<Vec<String>> {
+    let mut languages: Vec<&LanguageReport> = report.languages.iter().collect();
+    languages.sort_by(|a, b| options.sort.compare(a, b));
+
+    let mut rows = vec![HEADER.iter().map(|h| h.to_string()).collect()];
+    for lang in languages {
+        rows.push(vec![
+            lang.name.clone(),
+            lang.files.to_string(),
+            lang.lines.code.to_string(),
+            lang.lines.comments.to_string(),
+            lang.lines.blanks.to_string(),
+            lang.tests.to_string(),
+            lang.minified.to_string(),
+            lang.bytes.to_string(),
+            lang.percentage.map(percentage).unwrap_or_default(),
+        ]);
+    }
+
+    if options.totals {
+        let Totals {
+            files,
+            lines,
+            bytes,
+        } = report.totals;
+        let tests: u32 = report.languages.iter().map(|l| l.tests).sum();
+        let minified: u32 = report.languages.iter().map(|l| l.minified).sum();
+        let share: f64 = report.languages.iter().filter_map(|l| l.percentage).sum();
+
+        rows.push(vec![
+            "Total".to_string(),
+            files.to_string(),
+            lines.code.to_string(),
+            lines.comments.to_string(),
+            lines.blanks.to_string(),
+            tests.to_string(),
+            minified.to_string(),
+            bytes.to_string(),
+            percentage(share),
+        ]);
+    }
+    rows
+}
+
+fn percentage(share: f64) -> String {
+    format!("{:.2}", share)
+}
+
+/// Renders the File Stats table as CSV, quoting fields where needed.
+pub fn csv(report: &Report, options: TableOptions) -> String {
+    csv_rows(&table(report, options))
+}
+
+/// Renders the File Stats table as a GitHub flavored Markdown table, with the
+/// numeric columns right-aligned.
+pub fn markdown(report: &Report, options: TableOptions) -> String {
+    markdown_rows(&table(report, options))
+}
+
+pub(crate) fn csv_rows(rows: &[Vec<String>]) -> String {
+    let mut out = String::new();
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Lays out `rows` in columns, numbers right-aligned and text left-aligned,
+/// with a header rule when `markdown` is set.
+fn align(rows: &[Vec<String>], markdown: bool) -> Vec<Vec<String>> {
+    let escape = |cell: &str| match markdown {
+        true => cell.replace('|', "\\|"),
+        false => cell.to_string(),
+    };
+    let columns = rows.first().map_or(0, Vec::len);
+    let mut widths = vec![if markdown { 3 } else { 0 }; columns];
+    let numeric: Vec<bool> = (0..columns)
+        .map(|col| {
+            rows.iter().skip(1).all(|row| {
+                let cell = row[col].trim_start_matches(['+', '-']);
+                cell.chars().all(|c| c.is_ascii_digit() || c == '.')
+            })
+        })
+        .collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(escape(cell).chars().count());
+        }
+    }
+
+    let mut lines = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let cells = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(col, (cell, &width))| match numeric[col] {
+                true => format!("{:>width$}", escape(cell)),
+                false => format!("{:<width$}", escape(cell)),
+            })
+            .collect();
+        lines.push(cells);
+
+        if i == 0 && markdown {
+            let rules = widths
+                .iter()
+                .enumerate()
+                .map(|(col, &width)| match numeric[col] {
+                    true => format!("{}:", "-".repeat(width - 1)),
+                    false => format!(":{}", "-".repeat(width - 1)),
+                })
+                .collect();
+            lines.push(rules);
+        }
+    }
+    lines
+}
+
+pub(crate) fn markdown_rows(rows: &[Vec<String>]) -> String {
+    let mut out = String::new();
+    for cells in align(rows, true) {
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    out
+}
+
+/// Plain text table for the terminal, columns separated by two spaces.
+pub(crate) fn text_rows(rows: &[Vec<String>]) -> String {
+    let mut out = String::new();
+    for cells in align(rows, false) {
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::Category;
+    use crate::lines::Lines;
+    use crate::stats::Binaries;
+
+    fn language(name: &str, code: u32, bytes: u64, percentage: Option<f64>) -> LanguageReport {
+        LanguageReport {
+            name: name.to_string(),
+            category: Category::Programming,
+            files: 1,
+            tests: 0,
+            minified: 0,
+            lines: Lines {
+                code,
+                comments: 0,
+                blanks: 1,
+            },
+            bytes,
+            percentage,
+        }
+    }
+
+    fn report() -> Report {
+        Report {
+            version: crate::report::VERSION,
+            path: ".".to_string(),
+            totals: Totals {
+                files: 3,
+                lines: Lines {
+                    code: 60,
+                    comments: 0,
+                    blanks: 3,
+                },
+                bytes: 600,
+            },
+            languages: vec![
+                language("Rust", 10, 300, Some(75.0)),
+                language("TOML", 40, 200, None),
+                language("C, C++", 10, 100, Some(25.0)),
+            ],
+            binaries: Binaries::default(),
+            recent: Vec::new(),
+            git: None,
+        }
+    }
+
+    #[test]
+    fn exports_csv() {
+        let options = TableOptions {
+            sort: SortBy::Code,
+            totals: true,
+        };
+        assert_eq!(
+            csv(&report(), options),
+            "Language,Files,Code,Comments,Blanks,Tests,Minified,Bytes,Percentage\n\
+             TOML,1,40,0,1,0,0,200,\n\
+             \"C, C++\",1,10,0,1,0,0,100,25.00\n\
+             Rust,1,10,0,1,0,0,300,75.00\n\
+             Total,3,60,0,3,0,0,600,100.00\n"
+        );
+    }
+
+    #[test]
+    fn exports_markdown() {
+        let options = TableOptions {
+            sort: SortBy::Percentage,
+            totals: false,
+        };
+        let table = markdown(&report(), options);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[0],
+            "| Language | Files | Code | Comments | Blanks | Tests | Minified | Bytes | Percentage |"
+        );
+        assert_eq!(
+            lines[1],
+            "| :------- | ----: | ---: | -------: | -----: | ----: | -------: | ----: | ---------: |"
+        );
+        assert!(lines[2].starts_with("| Rust "));
+        assert!(lines[3].starts_with("| C, C++ "));
+        assert!(lines[4].starts_with("| TOML "));
+        assert!(lines[4].ends_with("|            |"));
+    }
+}
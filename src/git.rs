@@ -0,0 +1,288 @@
+use crate::languages::Languages;
+use crate::stats::{FileStat, Scan};
+use git2::{ObjectType, Repository, TreeWalkMode, TreeWalkResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Two revisions to compare, parsed from `old..new` or `old...new`.
+pub struct Range {
+    pub old: String,
+    pub new: String,
+    /// With three dots, `old` is replaced by the merge base of both sides, so
+    /// only the changes made on `new` count.
+    pub merge_base: bool,
+}
+
+impl Range {
+    /// Parses a git-style revision range. An empty side stands for `HEAD`.
+    pub fn parse(range: &str) -> Result<Range, String> {
+        let (old, new, merge_base) = match range.split_once("...") {
+            Some((old, new)) => (old, new, true),
+            None => match range.split_once("..") {
+                Some((old, new)) => (old, new, false),
+                None => {
+                    return Err(format!(
+                        "Expected a range like main..feature, got {}",
+                        range
+                    ))
+                }
+            },
+        };
+        let side = |rev: &str| match rev {
+            "" => "HEAD".to_string(),
+            rev => rev.to_string(),
+        };
+
+        Ok(Range {
+            old: side(old),
+            new: side(new),
+            merge_base,
+        })
+    }
+}
+
+/// Scans the tree of two revisions of the repository holding `path`, reading
+/// blobs from the object database instead of the working directory. Only the
+/// part of the tree under `path` is counted.
+pub fn scan_range(
+    path: &str,
+    range: &Range,
+    languages: &Languages,
+) -> Result<(Scan, Scan), String> {
+    let repo = Repository::discover(path).map_err(|e| e.message().to_string())?;
+    let prefix = prefix(&repo, path)?;
+
+    let commit = |rev: &str| {
+        repo.revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| format!("{}: {}", rev, e.message()))
+    };
+    let new = commit(&range.new)?;
+    let mut old = commit(&range.old)?;
+    if range.merge_base {
+        let base = repo
+            .merge_base(old.id(), new.id())
+            .map_err(|e| e.message().to_string())?;
+        old = repo
+            .find_commit(base)
+            .map_err(|e| e.message().to_string())?;
+    }
+
+    let old = scan_tree(
+        &repo,
+        &old.tree().map_err(|e| e.message().to_string())?,
+        &prefix,
+        languages,
+    )?;
+    let new = scan_tree(
+        &repo,
+        &new.tree().map_err(|e| e.message().to_string())?,
+        &prefix,
+        languages,
+    )?;
+    Ok((old, new))
+}
+
+/// Location of `path` inside the repository's working directory.
+pub(crate) fn prefix(repo: &Repository, path: &str) -> Result<PathBuf, String> {
+    let workdir = match repo.workdir() {
+        Some(workdir) => fs::canonicalize(workdir).map_err(|e| e.to_string())?,
+        None => return Ok(PathBuf::new()),
+    };
+    let path = fs::canonicalize(path).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(path
+        .strip_prefix(&workdir)
+        .unwrap_or(Path::new(""))
+        .to_path_buf())
+}
+
+/// Results of earlier runs, kept as JSON in the `pstat` directory of the
+/// repository's git directory so that later runs only do the new work.
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct Cache<T> {
+    #[serde(skip)]
+    file: PathBuf,
+    version: u32,
+    pub entries: T,
+}
+
+impl<T: Default + Serialize + DeserializeOwned> Cache<T> {
+    /// Reads the cache `name`, or starts an empty one when it is missing,
+    /// unreadable or was written with another `version`.
+    pub fn load(repo: &Repository, name: &str, version: u32) -> Cache<T> {
+        let file = repo.path().join("pstat").join(name);
+        let cache = fs::read_to_string(&file)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Cache<T>>(&json).ok())
+            .filter(|cache| cache.version == version);
+        Cache {
+            entries: cache.map(|cache| cache.entries).unwrap_or_default(),
+            file,
+            version,
+        }
+    }
+
+    /// Saving is best effort, a failure only costs the next run some time.
+    pub fn save(&self) {
+        if let (Some(dir), Ok(json)) = (self.file.parent(), serde_json::to_string(self)) {
+            let _ = fs::create_dir_all(dir).and_then(|_| fs::write(&self.file, json));
+        }
+    }
+}
+
+fn scan_tree(
+    repo: &Repository,
+    tree: &git2::Tree,
+    prefix: &Path,
+    languages: &Languages,
+) -> Result<Scan, String> {
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    let mut error = None;
+
+    tree.walk(TreeWalkMode::PreOrder, |parent, entry| {
+        let name = match entry.name() {
+            Ok(name) => name,
+            Err(_) => return TreeWalkResult::Skip,
+        };
+        let path = Path::new(parent).join(name);
+        let rel = match path.strip_prefix(prefix) {
+            Ok(rel) => rel.to_path_buf(),
+            // Outside of the scanned directory, but it may lead into it.
+            Err(_) if prefix.starts_with(&path) => return TreeWalkResult::Ok,
+            Err(_) => return TreeWalkResult::Skip,
+        };
+
+        match entry.kind() {
+            Some(ObjectType::Tree) if !rel.as_os_str().is_empty() => dirs.push(rel),
+            Some(ObjectType::Blob) => match repo.find_blob(entry.id()) {
+                Ok(blob) => files.push(FileStat::from_contents(
+                    &rel,
+                    blob.content().to_vec(),
+                    languages,
+                )),
+                Err(e) => {
+                    error = Some(e.message().to_string());
+                    return TreeWalkResult::Abort;
+                }
+            },
+            _ => {}
+        }
+        TreeWalkResult::Ok
+    })
+    .map_err(|e| error.clone().unwrap_or_else(|| e.message().to_string()))?;
+
+    Ok(Scan::new(files, dirs))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use git2::{Signature, Time};
+
+    /// Creates an empty repository in a fresh temporary directory.
+    pub(crate) fn repo(name: &str) -> (PathBuf, Repository) {
+        let root = std::env::temp_dir().join(format!("pstat-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let repo = Repository::init(&root).unwrap();
+        (root, repo)
+    }
+
+    /// Commits `files` on top of HEAD, replacing the whole tree.
+    pub(crate) fn commit(repo: &Repository, files: &[(&str, &str)], message: &str) {
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        commit_as(repo, files, message, &signature);
+    }
+
+    /// Like `commit`, with the given author and time.
+    pub(crate) fn commit_as(
+        repo: &Repository,
+        files: &[(&str, &str)],
+        message: &str,
+        signature: &Signature,
+    ) -> git2::Oid {
+        let mut index = repo.index().unwrap();
+        index.clear().unwrap();
+        let workdir = repo.workdir().unwrap();
+        for (file, contents) in files {
+            let path = workdir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+            index.add_path(Path::new(file)).unwrap();
+        }
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), signature, signature, message, &tree, &parents)
+            .unwrap()
+    }
+
+    /// Signature of `name` at `seconds` since the epoch, in UTC.
+    pub(crate) fn at(name: &str, seconds: i64) -> Signature<'static> {
+        let email = format!("{}@example.com", name.to_lowercase().replace(' ', "."));
+        Signature::new(name, &email, &Time::new(seconds, 0)).unwrap()
+    }
+
+    #[test]
+    fn parses_ranges() {
+        let range = Range::parse("main..feature").unwrap();
+        assert_eq!(
+            (range.old.as_str(), range.new.as_str()),
+            ("main", "feature")
+        );
+        assert!(!range.merge_base);
+
+        let range = Range::parse("main...").unwrap();
+        assert_eq!((range.old.as_str(), range.new.as_str()), ("main", "HEAD"));
+        assert!(range.merge_base);
+
+        assert!(Range::parse("main").is_err());
+    }
+
+    #[test]
+    fn scans_revisions_from_the_object_database() {
+        let (root, repo) = repo("rev");
+
+        commit(
+            &repo,
+            &[
+                ("src/main.rs", "fn main() {}\n"),
+                ("run", "#!/bin/sh\necho hi\n"),
+            ],
+            "first",
+        );
+        commit(
+            &repo,
+            &[
+                ("src/main.rs", "fn main() {}\n// done\n"),
+                ("src/lib.rs", "pub fn f() {}\n"),
+                ("docs/notes.py", "x = 1\n"),
+            ],
+            "second",
+        );
+        // Uncommitted changes don't count.
+        fs::write(root.join("src/lib.rs"), "garbage\n\n\n").unwrap();
+
+        let languages = Languages::builtin();
+        let range = Range::parse("HEAD~1..HEAD").unwrap();
+        let (old, new) = scan_range(root.to_str().unwrap(), &range, &languages).unwrap();
+
+        assert_eq!(old.file_counts["Rust"].files, 1);
+        assert_eq!(old.file_counts["Shell"].lines.code, 1);
+        assert_eq!(new.file_counts["Rust"].files, 2);
+        assert_eq!(new.file_counts["Rust"].lines.comments, 1);
+        assert_eq!(new.file_counts["Rust"].lines.code, 2);
+        assert!(!new.file_counts.contains_key("Shell"));
+        assert!(new.dirs.contains(&PathBuf::from("docs")));
+
+        let sub = root.join("src");
+        let (_, new) = scan_range(sub.to_str().unwrap(), &range, &languages).unwrap();
+        assert_eq!(new.files.len(), 2);
+        assert!(!new.file_counts.contains_key("Python"));
+        assert_eq!(new.files[0].path.parent(), Some(Path::new("")));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+//! The commit graph of a log, drawn from commit parents with one row per
+//! commit.
+
+/// Lanes of the graph, carried from one row to the next so that a log can be
+/// drawn a page at a time.
+#[derive(Clone, Debug, Default)]
+pub struct Graph {
+    /// The commit each lane leads to, `None` for free lanes.
+    lanes: Vec<Option<String>>,
+}
+
+impl Graph {
+    /// The graph in front of commit `id`. Commits have to come after all of
+    /// their children, like in `git log --topo-order`.
+    pub fn row(&mut self, id: &str, parents: &[String]) -> String {
+        let mine = |lane: &Option<String>| lane.as_deref() == Some(id);
+        let col = match self.lanes.iter().position(mine) {
+            Some(col) => col,
+            None => self.free_lane(&[]),
+        };
+
+        let mut cells: Vec<char> = self
+            .lanes
+            .iter()
+            .map(|lane| if lane.is_some() { '│' } else { ' ' })
+            .collect();
+        let mut joins = vec![false; cells.len()];
+        cells[col] = '●';
+
+        // Other lanes leading here end in this row.
+        let ended: Vec<usize> = (0..self.lanes.len())
+            .filter(|&lane| lane != col && mine(&self.lanes[lane]))
+            .collect();
+        for &lane in &ended {
+            self.lanes[lane] = None;
+            cells[lane] = if lane > col { '╯' } else { '╰' };
+            join(&mut cells, &mut joins, col, lane);
+        }
+
+        self.lanes[col] = parents.first().cloned();
+        for parent in parents.iter().skip(1) {
+            let (lane, glyph) = match self.lanes.iter().position(|l| l.as_ref() == Some(parent)) {
+                Some(lane) => (lane, if lane > col { '┤' } else { '├' }),
+                None => {
+                    let lane = self.free_lane(&ended);
+                    self.lanes[lane] = Some(parent.clone());
+                    (lane, if lane > col { '╮' } else { '╭' })
+                }
+            };
+            if lane >= cells.len() {
+                cells.resize(lane + 1, ' ');
+                joins.resize(lane + 1, false);
+            }
+            cells[lane] = glyph;
+            join(&mut cells, &mut joins, col, lane);
+        }
+
+        while self.lanes.last() == Some(&None) {
+            self.lanes.pop();
+        }
+
+        let mut row = String::new();
+        for (cell, joined) in cells.iter().zip(&joins) {
+            row.push(*cell);
+            row.push(if *joined { '─' } else { ' ' });
+        }
+        row.trim_end().to_string()
+    }
+
+    /// The first free lane that didn't end in the current row, adding one if
+    /// there is none.
+    fn free_lane(&mut self, ended: &[usize]) -> usize {
+        let free =
+            (0..self.lanes.len()).find(|lane| self.lanes[*lane].is_none() && !ended.contains(lane));
+        free.unwrap_or_else(|| {
+            self.lanes.push(None);
+            self.lanes.len() - 1
+        })
+    }
+}
+
+/// Draws a horizontal line between the cells `a` and `b`, crossing the
+/// lanes in between. `joins[i]` is the gap after cell `i`.
+fn join(cells: &mut [char], joins: &mut [bool], a: usize, b: usize) {
+    let (from, to) = (a.min(b), a.max(b));
+    for cell in &mut cells[from + 1..to] {
+        *cell = match *cell {
+            '│' => '┼',
+            ' ' => '─',
+            other => other,
+        };
+    }
+    for gap in &mut joins[from..to] {
+        *gap = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(commits: &[(&str, &[&str])]) -> Vec<String> {
+        let mut graph = Graph::default();
+        commits
+            .iter()
+            .map(|(id, parents)| {
+                let parents: Vec<String> = parents.iter().map(|p| p.to_string()).collect();
+                graph.row(id, &parents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draws_linear_history() {
+        assert_eq!(draw(&[("b", &["a"]), ("a", &[])]), ["●", "●"]);
+    }
+
+    #[test]
+    fn draws_branches_and_merges() {
+        let rows = draw(&[("m", &["c", "b"]), ("c", &["a"]), ("b", &["a"]), ("a", &[])]);
+        assert_eq!(rows, ["●─╮", "● │", "│ ●", "●─╯"]);
+
+        // A merge whose second parent is already on another lane.
+        let rows = draw(&[
+            ("x", &["m"]),
+            ("y", &["b"]),
+            ("m", &["a", "b"]),
+            ("b", &["a"]),
+            ("a", &[]),
+        ]);
+        assert_eq!(rows, ["●", "│ ●", "●─┤", "│ ●", "●─╯"]);
+    }
+}
@@ -0,0 +1,396 @@
+use crate::git;
+use crate::languages::Languages;
+use crate::lines::{self, Lines};
+use crate::{dates, detect, export};
+use git2::{Oid, Repository, Sort, Tree, TreeWalkMode, TreeWalkResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Version of the history document and of its cache. Bumped when the way
+/// lines are counted changes, which also throws away cached trees.
+pub const VERSION: u32 = 1;
+
+/// Which commits of the first-parent history are sampled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sampling {
+    /// The last commit of every day.
+    Day,
+    /// The last commit of every week, weeks starting on Monday.
+    #[default]
+    Week,
+    /// Every tagged commit.
+    Tag,
+}
+
+impl Sampling {
+    pub const NAMES: [&'static str; 3] = ["day", "week", "tag"];
+}
+
+impl FromStr for Sampling {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Sampling, String> {
+        match name {
+            "day" => Ok(Sampling::Day),
+            "week" => Ok(Sampling::Week),
+            "tag" => Ok(Sampling::Tag),
+            _ => Err(format!("Unknown sampling: {}", name)),
+        }
+    }
+}
+
+/// Line totals of every language at one commit.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Point {
+    pub commit: String,
+    /// Commit date in the committer's time zone, `YYYY-MM-DD`.
+    pub date: String,
+    /// Commit time in seconds since the Unix epoch.
+    pub time: i64,
+    /// Tag pointing at the commit, when sampling by tag.
+    pub tag: Option<String>,
+    pub languages: BTreeMap<String, Lines>,
+}
+
+/// Lines of code over time, oldest point first.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct History {
+    pub version: u32,
+    pub sampling: Sampling,
+    pub points: Vec<Point>,
+}
+
+/// Language totals of already counted trees by tree id and fingerprint of
+/// the language definitions, so that later runs only count new trees.
+type Cache = git::Cache<HashMap<String, BTreeMap<String, Lines>>>;
+
+/// Counts the lines of each language along the first-parent history of HEAD
+/// in the repository holding `path`, only looking at files under `path`.
+pub fn history(path: &str, sampling: Sampling, languages: &Languages) -> Result<History, String> {
+    let repo = Repository::discover(path).map_err(|e| e.message().to_string())?;
+    let prefix = git::prefix(&repo, path)?;
+    let mut cache = Cache::load(&repo, "history.json", VERSION);
+    let mut blobs = HashMap::new();
+
+    let mut points = Vec::new();
+    for (id, tag) in samples(&repo, sampling).map_err(|e| e.message().to_string())? {
+        let commit = repo.find_commit(id).map_err(|e| e.message().to_string())?;
+        let tree = subtree(
+            &repo,
+            &commit.tree().map_err(|e| e.message().to_string())?,
+            &prefix,
+        );
+
+        let languages = match &tree {
+            Some(tree) => {
+                let key = format!("{}:{:016x}", tree.id(), languages.fingerprint());
+                match cache.entries.get(&key) {
+                    Some(totals) => totals.clone(),
+                    None => {
+                        let totals = count_tree(&repo, tree, languages, &mut blobs)?;
+                        cache.entries.insert(key, totals.clone());
+                        totals
+                    }
+                }
+            }
+            // The directory didn't exist yet.
+            None => BTreeMap::new(),
+        };
+
+        let time = commit.committer().when();
+        points.push(Point {
+            commit: id.to_string(),
+            date: dates::format(time.seconds() + time.offset_minutes() as i64 * 60),
+            time: time.seconds(),
+            tag,
+            languages,
+        });
+    }
+
+    cache.save();
+    Ok(History {
+        version: VERSION,
+        sampling,
+        points,
+    })
+}
+
+/// Sampled commits of the first-parent history, oldest first, with the tag
+/// that picked them.
+fn samples(
+    repo: &Repository,
+    sampling: Sampling,
+) -> Result<Vec<(Oid, Option<String>)>, git2::Error> {
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+    walk.simplify_first_parent()?;
+    walk.set_sorting(Sort::TOPOLOGICAL)?;
+    // Newest first.
+    let first_parent = walk.collect::<Result<Vec<Oid>, _>>()?;
+
+    if sampling == Sampling::Tag {
+        let positions: HashMap<Oid, usize> = first_parent
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect();
+        let mut tagged = Vec::new();
+        let names = repo.tag_names(None)?;
+        for name in names.iter().filter_map(|name| name.ok().flatten()) {
+            // Tags can point at trees or blobs too, and tags on other
+            // branches don't belong to this history.
+            let commit = match repo
+                .find_reference(&format!("refs/tags/{}", name))
+                .and_then(|tag| tag.peel_to_commit())
+            {
+                Ok(commit) => commit,
+                Err(_) => continue,
+            };
+            if let Some(&i) = positions.get(&commit.id()) {
+                tagged.push((i, commit.id(), name.to_string()));
+            }
+        }
+        tagged.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.2.cmp(&b.2)));
+        tagged.dedup_by_key(|(_, id, _)| *id);
+        return Ok(tagged
+            .into_iter()
+            .map(|(_, id, tag)| (id, Some(tag)))
+            .collect());
+    }
+
+    // Newest first, so the first commit of each period is its last one.
+    let mut picked: Vec<(Oid, Option<String>)> = Vec::new();
+    let mut last_period = None;
+    for id in first_parent {
+        let time = repo.find_commit(id)?.committer().when();
+        let days = (time.seconds() + time.offset_minutes() as i64 * 60).div_euclid(dates::DAY);
+        let period = match sampling {
+            Sampling::Day => days,
+            // 1970-01-05 was a Monday.
+            _ => (days - 4).div_euclid(7),
+        };
+        if last_period != Some(period) {
+            picked.push((id, None));
+            last_period = Some(period);
+        }
+    }
+    picked.reverse();
+    Ok(picked)
+}
+
+fn subtree<'r>(repo: &'r Repository, tree: &Tree<'r>, prefix: &Path) -> Option<Tree<'r>> {
+    if prefix.as_os_str().is_empty() {
+        return Some(tree.clone());
+    }
+    let entry = tree.get_path(prefix).ok()?;
+    entry.to_object(repo).ok()?.into_tree().ok()
+}
+
+/// Sums up the lines of every language in `tree`. Blobs are counted once per
+/// run and file name, since most files don't change between samples.
+fn count_tree(
+    repo: &Repository,
+    tree: &Tree,
+    languages: &Languages,
+    blobs: &mut HashMap<(Oid, String), Option<(String, Lines)>>,
+) -> Result<BTreeMap<String, Lines>, String> {
+    let mut totals: BTreeMap<String, Lines> = BTreeMap::new();
+    let mut error = None;
+
+    tree.walk(TreeWalkMode::PreOrder, |_, entry| {
+        if entry.kind() != Some(git2::ObjectType::Blob) {
+            return TreeWalkResult::Ok;
+        }
+        let name = match entry.name() {
+            Ok(name) => name.to_string(),
+            Err(_) => return TreeWalkResult::Ok,
+        };
+
+        let key = (entry.id(), name);
+        if !blobs.contains_key(&key) {
+            let blob = match repo.find_blob(entry.id()) {
+                Ok(blob) => blob,
+                Err(e) => {
+                    error = Some(e.message().to_string());
+                    return TreeWalkResult::Abort;
+                }
+            };
+            let contents = blob.content();
+            let counted = languages
+                .detect_contents(Path::new(&key.1), contents)
+                .filter(|_| !detect::is_binary(contents))
+                .map(|language| {
+                    let text = detect::decode(contents.to_vec());
+                    (
+                        language.name.clone(),
+                        lines::classify(&text, &language.syntax),
+                    )
+                });
+            blobs.insert(key.clone(), counted);
+        }
+
+        if let Some((language, lines)) = &blobs[&key] {
+            totals.entry(language.clone()).or_default().add(lines);
+        }
+        TreeWalkResult::Ok
+    })
+    .map_err(|e| error.clone().unwrap_or_else(|| e.message().to_string()))?;
+
+    Ok(totals)
+}
+
+/// Renders the history in long format, one row per point and language.
+pub fn csv(history: &History) -> String {
+    let header = [
+        "date", "commit", "tag", "language", "code", "comments", "blanks",
+    ];
+    let mut rows = vec![header.iter().map(|h| h.to_string()).collect()];
+
+    for point in &history.points {
+        for (language, lines) in &point.languages {
+            rows.push(vec![
+                point.date.clone(),
+                point.commit.clone(),
+                point.tag.clone().unwrap_or_default(),
+                language.clone(),
+                lines.code.to_string(),
+                lines.comments.to_string(),
+                lines.blanks.to_string(),
+            ]);
+        }
+    }
+    export::csv_rows(&rows)
+}
+
+fn summary(history: &History) -> Vec<Vec<String>> {
+    let header = ["Date", "Commit", "Tag", "Code", "Comments", "Blanks"];
+    let mut rows = vec![header.iter().map(|h| h.to_string()).collect()];
+
+    for point in &history.points {
+        let mut total = Lines::default();
+        point.languages.values().for_each(|lines| total.add(lines));
+        rows.push(vec![
+            point.date.clone(),
+            point.commit.chars().take(7).collect(),
+            point.tag.clone().unwrap_or_default(),
+            total.code.to_string(),
+            total.comments.to_string(),
+            total.blanks.to_string(),
+        ]);
+    }
+    rows
+}
+
+/// Renders the line totals of every point as a plain text table.
+pub fn text(history: &History) -> String {
+    if history.points.is_empty() {
+        return "No commits found.\n".to_string();
+    }
+    export::text_rows(&summary(history))
+}
+
+pub fn markdown(history: &History) -> String {
+    export::markdown_rows(&summary(history))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::DAY;
+    use crate::git::tests::{at, commit_as, repo};
+    use crate::languages::{Category, Language};
+    use crate::lines::Syntax;
+    use std::fs;
+
+    /// Monday 2024-01-01 12:00 UTC.
+    const MONDAY: i64 = 1_704_110_400;
+
+    #[test]
+    fn samples_history_and_caches_trees() {
+        let (root, repo) = repo("history");
+        let days = [0, 2, 6, 7, 15];
+        for (i, day) in days.iter().enumerate() {
+            let code = "fn f() {}\n".repeat(i + 1);
+            let files = [("src/lib.rs", code.as_str()), ("notes.py", "# notes\n")];
+            let id = commit_as(&repo, &files, "work", &at("Ada", MONDAY + day * DAY));
+            if i == 1 {
+                let object = repo.find_object(id, None).unwrap();
+                repo.tag_lightweight("v1", &object, false).unwrap();
+            }
+        }
+
+        let languages = Languages::builtin();
+        let path = root.to_str().unwrap();
+
+        let weekly = history(path, Sampling::Week, &languages).unwrap();
+        let dates: Vec<&str> = weekly.points.iter().map(|p| p.date.as_str()).collect();
+        assert_eq!(dates, ["2024-01-07", "2024-01-08", "2024-01-16"]);
+        let code: Vec<u32> = weekly
+            .points
+            .iter()
+            .map(|p| p.languages["Rust"].code)
+            .collect();
+        assert_eq!(code, [3, 4, 5]);
+        assert_eq!(weekly.points[0].languages["Python"].comments, 1);
+
+        let daily = history(path, Sampling::Day, &languages).unwrap();
+        assert_eq!(daily.points.len(), 5);
+
+        let tagged = history(path, Sampling::Tag, &languages).unwrap();
+        assert_eq!(tagged.points.len(), 1);
+        assert_eq!(tagged.points[0].tag.as_deref(), Some("v1"));
+        assert_eq!(tagged.points[0].languages["Rust"].code, 2);
+
+        // Tags on other branches are left out.
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let signature = at("Ada", MONDAY + 20 * DAY);
+        let tree = head.tree().unwrap();
+        let side = repo
+            .commit(None, &signature, &signature, "side", &tree, &[&head])
+            .unwrap();
+        let object = repo.find_object(side, None).unwrap();
+        repo.tag_lightweight("side", &object, false).unwrap();
+        let tagged = history(path, Sampling::Tag, &languages).unwrap();
+        assert_eq!(tagged.points.len(), 1);
+
+        // Only the subdirectory is counted, and its trees are cached too.
+        let src = root.join("src");
+        let src = history(src.to_str().unwrap(), Sampling::Week, &languages).unwrap();
+        assert!(src
+            .points
+            .iter()
+            .all(|p| !p.languages.contains_key("Python")));
+        let mut cache = Cache::load(&repo, "history.json", VERSION);
+        // The five root trees and the three sampled `src` trees.
+        assert_eq!(cache.entries.len(), 5 + 3);
+
+        // Cached totals are used as they are.
+        for totals in cache.entries.values_mut() {
+            totals.insert("Cached".to_string(), Lines::default());
+        }
+        cache.save();
+        let again = history(path, Sampling::Week, &languages).unwrap();
+        assert!(again
+            .points
+            .iter()
+            .all(|p| p.languages.contains_key("Cached")));
+
+        // Other language definitions count the trees again.
+        let rust = Language {
+            name: "Rust".to_string(),
+            category: Category::Programming,
+            extensions: vec!["rs".to_string()],
+            filenames: Vec::new(),
+            interpreters: Vec::new(),
+            aliases: Vec::new(),
+            syntax: Syntax::default(),
+        };
+        let other = history(path, Sampling::Week, &Languages::new(vec![rust])).unwrap();
+        assert!(other.points.iter().all(|p| p.languages.keys().eq(["Rust"])));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,335 @@
+use crate::export::{self, SortBy, TableOptions};
+use crate::languages;
+use crate::report::{Git, Report};
+use crate::stats::human_bytes;
+use crate::tree::Node;
+use std::fmt::Write;
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2rem auto; max-width: 72rem; padding: 0 1rem; color: #1f2328; }
+h1 { font-size: 1.6rem; margin-bottom: .2rem; }
+h2 { font-size: 1.2rem; border-bottom: 1px solid #d0d7de; padding-bottom: .3rem; margin-top: 2rem; }
+.summary { color: #59636e; margin-top: 0; }
+.columns { display: grid; grid-template-columns: 1fr 1fr; gap: 2rem; }
+table { border-collapse: collapse; width: 100%; font-size: .9rem; }
+th, td { padding: .3rem .6rem; border-bottom: 1px solid #d0d7de; text-align: right; }
+th:first-child, td:first-child { text-align: left; }
+tr.total td { font-weight: bold; border-top: 2px solid #d0d7de; }
+td.left { text-align: left; }
+code, .tree { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: .85rem; }
+.tree ul { list-style: none; margin: 0; padding-left: 1.2rem; border-left: 1px solid #d0d7de; }
+.tree > ul { border-left: none; padding-left: 0; }
+.tree summary { cursor: pointer; font-weight: bold; }
+.meta { color: #59636e; font-weight: normal; margin-left: .5rem; }
+.dot { display: inline-block; width: .6rem; height: .6rem; border-radius: 50%; margin-right: .4rem; background: #d0d7de; }
+.current { font-weight: bold; }
+.muted { color: #59636e; }
+"#;
+
+/// Palette for languages without a GitHub color of their own.
+const PALETTE: [&str; 8] = [
+    "#e06c75", "#98c379", "#e5c07b", "#61afef", "#c678dd", "#56b6c2", "#d19a66", "#abb2bf",
+];
+
+/// Renders a self-contained HTML page with the contents of the Home and Git
+/// tabs. Styles and charts are inlined, so the page works offline.
+pub fn render(report: &Report, tree: &Node) -> String {
+    let mut out = String::new();
+    let title = format!("pstat - {}", report.path);
+
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(out, "<title>{}</title>", escape(&title));
+    let _ = writeln!(out, "<style>{}</style>\n</head>\n<body>", STYLE);
+    let _ = writeln!(out, "<h1>{}</h1>", escape(&title));
+    let _ = writeln!(
+        out,
+        "<p class=\"summary\">{} files, {} lines of code, {}</p>",
+        report.totals.files,
+        report.totals.lines.code,
+        human_bytes(report.totals.bytes)
+    );
+
+    out.push_str("<h2>Language Distribution</h2>\n");
+    out.push_str(&distribution(report));
+
+    out.push_str("<h2>File Stats</h2>\n");
+    out.push_str(&file_stats(report));
+
+    out.push_str("<div class=\"columns\">\n<section>\n<h2>Project Tree</h2>\n");
+    out.push_str("<div class=\"tree\">\n<ul>\n");
+    tree_node(tree, true, &mut out);
+    out.push_str("</ul>\n</div>\n</section>\n");
+
+    out.push_str("<section>\n<h2>Recently Modified</h2>\n<ol>\n");
+    for name in &report.recent {
+        let _ = writeln!(out, "<li><code>{}</code></li>", escape(name));
+    }
+    out.push_str("</ol>\n</section>\n</div>\n");
+
+    match &report.git {
+        Some(git) => out.push_str(&git_summary(git)),
+        None => out.push_str("<h2>Git</h2>\n<p class=\"muted\">Not a git repository.</p>\n"),
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn color(language: &str) -> String {
+    match languages::color(language) {
+        Some((r, g, b)) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        None => PALETTE[languages::name_hash(language) % PALETTE.len()].to_string(),
+    }
+}
+
+/// Horizontal bar chart of the languages that make up the distribution.
+fn distribution(report: &Report) -> String {
+    let mut shares: Vec<(&str, f64)> = report
+        .languages
+        .iter()
+        .filter_map(|l| Some((l.name.as_str(), l.percentage?)))
+        .collect();
+    if shares.is_empty() {
+        return "<p class=\"muted\">No programming or markup languages found.</p>\n".to_string();
+    }
+    shares.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    let (row, label, bar) = (26, 160.0, 560.0);
+    let height = shares.len() * row;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"800\" height=\"{}\" role=\"img\" aria-label=\"Language distribution\" font-size=\"13\">\n",
+        height
+    );
+    for (i, (name, share)) in shares.iter().enumerate() {
+        let y = i * row;
+        let width = (share / 100.0 * bar).max(1.0);
+        let _ = writeln!(
+            svg,
+            "<text x=\"0\" y=\"{}\">{}</text><rect x=\"{}\" y=\"{}\" width=\"{:.1}\" height=\"16\" rx=\"3\" fill=\"{}\"/><text x=\"{:.1}\" y=\"{}\" fill=\"#59636e\">{:.1}%</text>",
+            y + 17,
+            escape(name),
+            label,
+            y + 4,
+            width,
+            color(name),
+            label + width + 6.0,
+            y + 17,
+            share
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// The same table as `--format csv`, sorted by size with a totals row.
+fn file_stats(report: &Report) -> String {
+    let options = TableOptions {
+        sort: SortBy::Bytes,
+        totals: true,
+    };
+    let mut rows = export::table(report, options);
+    // Binary files go between the languages and the totals, like in the TUI.
+    let totals = rows.pop();
+    let languages = rows.len();
+    if report.binaries.files > 0 {
+        let mut binary = vec!["Binary".to_string(), report.binaries.files.to_string()];
+        binary.extend(["-"; 5].map(String::from));
+        binary.extend([report.binaries.bytes.to_string(), String::new()]);
+        rows.push(binary);
+    }
+    rows.extend(totals);
+    let last = rows.len() - 1;
+    let mut out = String::from("<table>\n");
+
+    for (i, row) in rows.iter().enumerate() {
+        let (tag, class) = match i {
+            0 => ("th", ""),
+            i if i == last => ("td", " class=\"total\""),
+            i if i >= languages => ("td", " class=\"muted\""),
+            _ => ("td", ""),
+        };
+        let _ = write!(out, "<tr{}>", class);
+        for (col, cell) in row.iter().enumerate() {
+            if col == 0 && i > 0 && i < languages {
+                let _ = write!(
+                    out,
+                    "<td><span class=\"dot\" style=\"background:{}\"></span>{}</td>",
+                    color(cell),
+                    escape(cell)
+                );
+            } else {
+                let _ = write!(out, "<{0}>{1}</{0}>", tag, escape(cell));
+            }
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+    out
+}
+
+/// Directories become collapsible `<details>`, open at the top level only.
+fn tree_node(node: &Node, root: bool, out: &mut String) {
+    if node.is_dir {
+        let _ = write!(
+            out,
+            "<li><details{}><summary>{}<span class=\"meta\">{} file{}, {} lines, {}</span></summary>\n<ul>\n",
+            if root { " open" } else { "" },
+            escape(&node.name),
+            node.files,
+            if node.files == 1 { "" } else { "s" },
+            node.lines.total(),
+            human_bytes(node.bytes)
+        );
+        for child in &node.children {
+            tree_node(child, false, out);
+        }
+        out.push_str("</ul>\n</details></li>\n");
+        return;
+    }
+
+    match &node.language {
+        Some(language) => {
+            let _ = writeln!(
+                out,
+                "<li><span class=\"dot\" style=\"background:{}\" title=\"{}\"></span>{}<span class=\"meta\">{} lines</span></li>",
+                color(language),
+                escape(language),
+                escape(&node.name),
+                node.lines.total()
+            );
+        }
+        None => {
+            let _ = writeln!(
+                out,
+                "<li><span class=\"dot\"></span>{}</li>",
+                escape(&node.name)
+            );
+        }
+    }
+}
+
+fn git_summary(git: &Git) -> String {
+    let mut out =
+        String::from("<h2>Git</h2>\n<div class=\"columns\">\n<section>\n<h3>Branches</h3>\n<ul>\n");
+    for branch in &git.branches {
+        let current = git.branch.as_ref() == Some(branch);
+        let _ = writeln!(
+            out,
+            "<li{}><code>{}</code></li>",
+            if current { " class=\"current\"" } else { "" },
+            escape(branch)
+        );
+    }
+    out.push_str("</ul>\n</section>\n<section>\n<h3>Status</h3>\n");
+
+    if git.status.is_empty() {
+        out.push_str("<p class=\"muted\">Nothing to commit, working tree clean.</p>\n");
+    } else {
+        out.push_str("<table>\n");
+        for change in &git.status {
+            let _ = writeln!(
+                out,
+                "<tr><td><code>{}</code></td><td class=\"left\"><code>{}</code></td></tr>",
+                escape(&change.status),
+                escape(&change.path)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</section>\n</div>\n<h3>Commit Log</h3>\n");
+
+    if git.log.is_empty() {
+        out.push_str("<p class=\"muted\">No commits yet.</p>\n");
+        return out;
+    }
+    out.push_str("<table>\n<tr><th>Commit</th><th class=\"left\">Summary</th><th class=\"left\">Author</th><th>Date</th></tr>\n");
+    for commit in &git.log {
+        let _ = writeln!(
+            out,
+            "<tr><td><code>{}</code></td><td class=\"left\">{}</td><td class=\"left\">{}</td><td>{}</td></tr>",
+            escape(commit.id.get(..7).unwrap_or(&commit.id)),
+            escape(&commit.summary),
+            escape(&commit.author),
+            escape(&commit.date)
+        );
+    }
+    out.push_str("</table>\n");
+    out
+}
+
+/// Escapes text for use in HTML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::{Category, Variant};
+    use crate::lines::Lines;
+    use crate::stats::{FileStat, Scan};
+    use crate::tree;
+    use std::path::PathBuf;
+
+    #[test]
+    fn renders_offline_page() {
+        let scan = Scan::new(
+            vec![
+                FileStat {
+                    path: PathBuf::from("src/<main>.rs"),
+                    language: Some("Rust".to_string()),
+                    category: Category::Programming,
+                    variant: Variant::Source,
+                    lines: Lines {
+                        code: 3,
+                        comments: 0,
+                        blanks: 1,
+                    },
+                    bytes: 40,
+                    binary: false,
+                    modified: 0,
+                },
+                FileStat {
+                    path: PathBuf::from("logo.png"),
+                    language: None,
+                    category: Category::Programming,
+                    variant: Variant::Source,
+                    lines: Lines::default(),
+                    bytes: 2048,
+                    binary: true,
+                    modified: 0,
+                },
+            ],
+            vec![PathBuf::from("src")],
+        );
+        let report = Report::new("demo & co", &scan, 5, None);
+        let page = render(&report, &tree::build("demo & co", &scan));
+
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains("<title>pstat - demo &amp; co</title>"));
+        assert!(page.contains("&lt;main&gt;.rs"));
+        assert!(page.contains("fill=\"#dea584\""));
+        assert!(page.contains("Not a git repository."));
+        // Binary files come before the totals, like in the TUI.
+        let binary = page
+            .find("<tr class=\"muted\"><td>Binary</td><td>1</td><td>-</td>")
+            .unwrap();
+        assert!(binary < page.find("<tr class=\"total\">").unwrap());
+        assert!(page.contains("<td>2048</td><td></td></tr>\n<tr class=\"total\">"));
+        // Nothing is loaded from elsewhere.
+        assert!(!page.contains("<script"));
+        assert!(!page.contains("<link"));
+        assert_eq!(page.matches("http").count(), 1);
+    }
+}
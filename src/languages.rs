@@ -0,0 +1,345 @@
+use crate::detect;
+use crate::lines::Syntax;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Built-in language table, compiled into the binary.
+const BUILTIN: &str = include_str!("languages.toml");
+
+/// Kind of language, following GitHub linguist. Only programming and markup
+/// languages show up in the language distribution.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    #[default]
+    Programming,
+    Markup,
+    Data,
+    Prose,
+    Config,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Language {
+    pub name: String,
+    #[serde(default)]
+    pub category: Category,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub filenames: Vec<String>,
+    #[serde(default)]
+    pub interpreters: Vec<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(flatten)]
+    pub syntax: Syntax,
+}
+
+#[derive(Deserialize)]
+struct Definitions {
+    #[serde(default)]
+    language: Vec<Language>,
+}
+
+/// Registry of known languages, looked up by file name or extension.
+#[derive(Clone)]
+pub struct Languages {
+    languages: Vec<Language>,
+    extensions: HashMap<String, usize>,
+    filenames: HashMap<String, usize>,
+    interpreters: HashMap<String, usize>,
+    modes: HashMap<String, usize>,
+    fingerprint: u64,
+}
+
+impl Languages {
+    /// Loads the built-in table and applies the user's `languages.toml` on top of it.
+    /// A user entry with the same name as a built-in one replaces it.
+    pub fn load() -> Result<Languages, String> {
+        let mut languages = builtin();
+
+        if let Some(path) = user_file().filter(|p| p.exists()) {
+            let contents = fs::read_to_string(&path)
+                .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+            let user = parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+            for lang in user {
+                match languages.iter().position(|l| l.name == lang.name) {
+                    Some(i) => languages[i] = lang,
+                    None => languages.push(lang),
+                }
+            }
+        }
+
+        Ok(Languages::new(languages))
+    }
+
+    /// The built-in table alone, ignoring the user's definitions.
+    pub fn builtin() -> Languages {
+        Languages::new(builtin())
+    }
+
+    pub fn new(languages: Vec<Language>) -> Languages {
+        let mut extensions = HashMap::new();
+        let mut filenames = HashMap::new();
+        let mut interpreters = HashMap::new();
+        let mut modes = HashMap::new();
+
+        for (i, lang) in languages.iter().enumerate() {
+            for ext in &lang.extensions {
+                extensions.insert(ext.to_lowercase(), i);
+            }
+            for name in &lang.filenames {
+                filenames.insert(name.clone(), i);
+            }
+            for name in &lang.interpreters {
+                interpreters.insert(name.clone(), i);
+            }
+            // Editor modes usually match the language name or one of its extensions.
+            let names = lang.extensions.iter().chain(&lang.aliases);
+            for name in names.chain([&lang.name, &lang.name.replace(' ', "-")]) {
+                modes.entry(name.to_lowercase()).or_insert(i);
+            }
+        }
+
+        let fingerprint = fnv1a(
+            serde_json::to_string(&languages)
+                .unwrap_or_default()
+                .as_bytes(),
+        );
+        Languages {
+            languages,
+            extensions,
+            filenames,
+            interpreters,
+            modes,
+            fingerprint,
+        }
+    }
+
+    /// Hash of the definitions, which changes whenever a language is added or
+    /// counted differently. Results cached across runs are keyed by it.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    pub fn by_filename(&self, name: &str) -> Option<&Language> {
+        self.filenames.get(name).map(|&i| &self.languages[i])
+    }
+
+    pub fn by_extension(&self, ext: &str) -> Option<&Language> {
+        self.extensions
+            .get(&ext.to_lowercase())
+            .map(|&i| &self.languages[i])
+    }
+
+    pub fn by_interpreter(&self, name: &str) -> Option<&Language> {
+        self.interpreters
+            .get(name)
+            .or_else(|| self.interpreters.get(detect::strip_version(name)))
+            .map(|&i| &self.languages[i])
+    }
+
+    pub fn by_mode(&self, mode: &str) -> Option<&Language> {
+        let mode = mode.strip_suffix("-mode").unwrap_or(mode);
+        self.modes.get(mode).map(|&i| &self.languages[i])
+    }
+
+    /// Detects the language of `path` from its file name, then its extension.
+    /// Compound extensions such as `d.ts` win over the final one, and
+    /// extensionless files are identified by their shebang or modeline.
+    pub fn detect(&self, path: &Path) -> Option<&Language> {
+        self.detect_with(path, || detect::read_head(path).ok())
+    }
+
+    /// Like `detect`, for a file that is already in memory, e.g. a git blob.
+    pub fn detect_contents(&self, path: &Path, contents: &[u8]) -> Option<&Language> {
+        self.detect_with(path, || {
+            let head = &contents[..contents.len().min(detect::HEAD_SIZE as usize)];
+            Some(String::from_utf8_lossy(head).into_owned())
+        })
+    }
+
+    /// Detection shared by files on disk and in memory. `head` returns the
+    /// start of the file, only read for extensionless files.
+    fn detect_with(&self, path: &Path, head: impl FnOnce() -> Option<String>) -> Option<&Language> {
+        let name = path.file_name()?.to_str()?;
+        if let Some(lang) = self.by_filename(name) {
+            return Some(lang);
+        }
+
+        // The leading dot of a dotfile doesn't start an extension.
+        let body = name.trim_start_matches('.');
+        let mut dots = body.match_indices('.').map(|(i, _)| i).peekable();
+
+        if dots.peek().is_none() {
+            let head = head()?;
+            return detect::shebang(&head)
+                .and_then(|interpreter| self.by_interpreter(&interpreter))
+                .or_else(|| self.by_mode(&detect::modeline(&head)?));
+        }
+        dots.find_map(|i| self.by_extension(&body[i + 1..]))
+    }
+}
+
+/// Kind of source file, told apart by naming conventions such as
+/// `app.min.js`, `index.test.ts`, `parser_test.go` or `test_utils.py`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    Source,
+    Test,
+    Minified,
+}
+
+pub fn variant(path: &Path) -> Variant {
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name.trim_start_matches('.'),
+        None => return Variant::Source,
+    };
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    let parts: Vec<&str> = stem.split('.').collect();
+    let last = parts.last().copied().unwrap_or(stem);
+
+    if parts.contains(&"min") || last.ends_with("-min") {
+        Variant::Minified
+    } else if parts
+        .iter()
+        .any(|p| matches!(*p, "test" | "tests" | "spec"))
+        || ["_test", "_spec", "Test", "Tests", "Spec"]
+            .iter()
+            .any(|suffix| last.ends_with(suffix))
+        || stem.starts_with("test_")
+    {
+        Variant::Test
+    } else {
+        Variant::Source
+    }
+}
+
+/// GitHub's color for common languages, as RGB.
+pub fn color(language: &str) -> Option<(u8, u8, u8)> {
+    match language {
+        "Rust" => Some((0xde, 0xa5, 0x84)),
+        "Python" => Some((0x35, 0x72, 0xa5)),
+        "JavaScript" | "JSX" => Some((0xf1, 0xe0, 0x5a)),
+        "TypeScript" | "TSX" | "TypeScript Declaration" => Some((0x31, 0x78, 0xc6)),
+        "Go" => Some((0x00, 0xad, 0xd8)),
+        "C" => Some((0x55, 0x55, 0x55)),
+        "C++" => Some((0xf3, 0x4b, 0x7d)),
+        "C#" => Some((0x17, 0x86, 0x00)),
+        "Java" => Some((0xb0, 0x72, 0x19)),
+        "Kotlin" => Some((0xa9, 0x7b, 0xff)),
+        "Swift" => Some((0xf0, 0x51, 0x38)),
+        "Ruby" => Some((0x70, 0x15, 0x16)),
+        "PHP" => Some((0x4f, 0x5d, 0x95)),
+        "HTML" => Some((0xe3, 0x4c, 0x26)),
+        "CSS" => Some((0x56, 0x3d, 0x7c)),
+        "SCSS" => Some((0xc6, 0x53, 0x8c)),
+        "Shell" => Some((0x89, 0xe0, 0x51)),
+        "Markdown" => Some((0x08, 0x3f, 0xa1)),
+        "JSON" => Some((0x29, 0x29, 0x29)),
+        "TOML" => Some((0x9c, 0x42, 0x21)),
+        "YAML" => Some((0xcb, 0x17, 0x1e)),
+        "Lua" => Some((0x00, 0x00, 0x80)),
+        "Haskell" => Some((0x5e, 0x50, 0x86)),
+        "Scala" => Some((0xc2, 0x2d, 0x40)),
+        "Elixir" => Some((0x6e, 0x4a, 0x7e)),
+        "Erlang" => Some((0xb8, 0x39, 0x98)),
+        "Dart" => Some((0x00, 0xb4, 0xab)),
+        "Vue" => Some((0x41, 0xb8, 0x83)),
+        "Zig" => Some((0xec, 0x91, 0x5c)),
+        _ => None,
+    }
+}
+
+/// Stable hash of a language name, used to give languages without a color of
+/// their own one from a palette.
+pub fn name_hash(language: &str) -> usize {
+    language
+        .bytes()
+        .fold(0usize, |h, b| h.wrapping_mul(31).wrapping_add(b as usize))
+}
+
+/// 64-bit FNV-1a, a hash that stays the same across platforms and Rust
+/// versions, unlike the standard library's.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn builtin() -> Vec<Language> {
+    parse(BUILTIN).expect("Built-in language table is invalid")
+}
+
+fn parse(contents: &str) -> Result<Vec<Language>, toml::de::Error> {
+    let defs: Definitions = toml::from_str(contents)?;
+    Ok(defs.language)
+}
+
+/// Location of the user's language definitions, `~/.config/pstat/languages.toml`.
+pub fn user_file() -> Option<PathBuf> {
+    let config = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+            PathBuf::from(home).join(".config")
+        }
+    };
+    Some(config.join("pstat").join("languages.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(name: &str) -> Option<String> {
+        let languages = Languages::builtin();
+        languages.detect(Path::new(name)).map(|l| l.name.clone())
+    }
+
+    #[test]
+    fn resolves_multi_dot_names() {
+        assert_eq!(detect("index.test.ts").as_deref(), Some("TypeScript"));
+        assert_eq!(detect("jquery.min.js").as_deref(), Some("JavaScript"));
+        assert_eq!(detect(".eslintrc.js").as_deref(), Some("JavaScript"));
+        assert_eq!(detect("v1.2/main.rs").as_deref(), Some("Rust"));
+        assert_eq!(
+            detect("types/index.d.ts").as_deref(),
+            Some("TypeScript Declaration")
+        );
+        assert_eq!(detect("archive.tar.gz"), None);
+    }
+
+    #[test]
+    fn classifies_variants() {
+        assert_eq!(variant(Path::new("app.min.js")), Variant::Minified);
+        assert_eq!(variant(Path::new("bootstrap-min.css")), Variant::Minified);
+        assert_eq!(variant(Path::new("index.test.ts")), Variant::Test);
+        assert_eq!(variant(Path::new("button.spec.tsx")), Variant::Test);
+        assert_eq!(variant(Path::new("parser_test.go")), Variant::Test);
+        assert_eq!(variant(Path::new("test_utils.py")), Variant::Test);
+        assert_eq!(variant(Path::new("StatsTest.java")), Variant::Test);
+        assert_eq!(variant(Path::new("main.rs")), Variant::Source);
+        assert_eq!(variant(Path::new(".eslintrc.js")), Variant::Source);
+    }
+
+    #[test]
+    fn fingerprints_definitions() {
+        // FNV-1a of `[]`, so the caches keyed by it survive toolchain upgrades.
+        assert_eq!(
+            Languages::new(Vec::new()).fingerprint(),
+            0x0961_2b07_b5ec_b5a5
+        );
+
+        let mut languages = builtin();
+        let before = Languages::new(languages.clone()).fingerprint();
+        assert_eq!(Languages::builtin().fingerprint(), before);
+        languages[0].syntax.nested = !languages[0].syntax.nested;
+        assert_ne!(Languages::new(languages).fingerprint(), before);
+    }
+}
@@ -0,0 +1,23 @@
+//! Project scanning behind the pstat TUI: language detection, line counting,
+//! the parallel directory walk, the project tree and machine-readable reports.
+
+pub mod activity;
+pub mod authors;
+pub mod backend;
+pub mod badge;
+pub mod branches;
+pub mod churn;
+pub mod dates;
+pub mod detect;
+pub mod export;
+pub mod git;
+pub mod graph;
+pub mod history;
+pub mod html;
+pub mod languages;
+pub mod lines;
+pub mod ownership;
+pub mod report;
+pub mod snapshot;
+pub mod stats;
+pub mod tree;
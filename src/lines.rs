@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+
+/// Comment and string syntax of a language, used to classify its lines.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Syntax {
+    pub line_comments: Vec<String>,
+    pub block_comments: Vec<(String, String)>,
+    pub nested: bool,
+    pub quotes: Vec<(String, String)>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lines {
+    pub code: u32,
+    pub comments: u32,
+    pub blanks: u32,
+}
+
+impl Lines {
+    pub fn total(&self) -> u32 {
+        self.code + self.comments + self.blanks
+    }
+
+    pub fn add(&mut self, other: &Lines) {
+        self.code += other.code;
+        self.comments += other.comments;
+        self.blanks += other.blanks;
+    }
+}
+
+/// Splits `contents` into code, comment and blank lines.
+///
+/// A line counts as code if anything other than whitespace appears outside of a
+/// comment, as a comment if it only holds comment text, and as blank otherwise.
+/// Comment markers inside string literals are ignored, and block comments nest
+/// when the syntax allows it.
+pub fn classify(contents: &str, syntax: &Syntax) -> Lines {
+    let mut lines = Lines::default();
+    // Index into `block_comments` of every open block comment, innermost last.
+    let mut blocks: Vec<usize> = Vec::new();
+    // Closing delimiter of the string literal we are inside of, if any.
+    let mut string: Option<&str> = None;
+
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            lines.blanks += 1;
+            continue;
+        }
+
+        let mut has_code = string.is_some();
+        let mut has_comment = !blocks.is_empty();
+        let mut i = 0;
+
+        while i < line.len() {
+            let rest = &line[i..];
+
+            if let Some(end) = string {
+                if let Some(escaped) = rest.strip_prefix('\\') {
+                    i += 1 + escaped.chars().next().map_or(0, char::len_utf8);
+                    continue;
+                }
+                if rest.starts_with(end) {
+                    string = None;
+                    i += end.len();
+                    continue;
+                }
+            } else if let Some(&open) = blocks.last() {
+                let end = &syntax.block_comments[open].1;
+                if rest.starts_with(end.as_str()) {
+                    blocks.pop();
+                    i += end.len();
+                    continue;
+                }
+                if syntax.nested {
+                    if let Some(inner) = block_start(rest, syntax) {
+                        blocks.push(inner);
+                        i += syntax.block_comments[inner].0.len();
+                        continue;
+                    }
+                }
+            } else {
+                if syntax
+                    .line_comments
+                    .iter()
+                    .any(|c| rest.starts_with(c.as_str()))
+                {
+                    has_comment = true;
+                    break;
+                }
+                if let Some(open) = block_start(rest, syntax) {
+                    has_comment = true;
+                    blocks.push(open);
+                    i += syntax.block_comments[open].0.len();
+                    continue;
+                }
+                if let Some((start, end)) = syntax
+                    .quotes
+                    .iter()
+                    .find(|q| rest.starts_with(q.0.as_str()))
+                {
+                    has_code = true;
+                    string = Some(end.as_str());
+                    i += start.len();
+                    continue;
+                }
+                if let Some(len) = char_literal(rest, syntax) {
+                    has_code = true;
+                    i += len;
+                    continue;
+                }
+                if !rest.starts_with(char::is_whitespace) {
+                    has_code = true;
+                }
+            }
+
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+
+        if has_comment && !has_code {
+            lines.comments += 1;
+        } else {
+            lines.code += 1;
+        }
+    }
+
+    lines
+}
+
+/// Length of the character literal `rest` starts with, like `'"'` or
+/// `'\''`, in languages where `'` doesn't quote strings. Rust lifetimes like
+/// `'a` aren't literals, since no `'` follows their first character.
+fn char_literal(rest: &str, syntax: &Syntax) -> Option<usize> {
+    if syntax.quotes.iter().any(|(start, _)| start == "'") {
+        return None;
+    }
+    let body = rest.strip_prefix('\'')?;
+    let len = match body.strip_prefix('\\') {
+        // The escaped character, then anything up to the closing quote, like
+        // in `'\u{1F600}'`.
+        Some(escaped) => {
+            let first = escaped.chars().next()?.len_utf8();
+            1 + first + escaped[first..].find('\'')?
+        }
+        None => {
+            let first = body.chars().next()?.len_utf8();
+            body[first..].starts_with('\'').then_some(first)?
+        }
+    };
+    Some(1 + len + 1)
+}
+
+fn block_start(rest: &str, syntax: &Syntax) -> Option<usize> {
+    syntax
+        .block_comments
+        .iter()
+        .position(|(start, _)| rest.starts_with(start.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust() -> Syntax {
+        Syntax {
+            line_comments: vec!["//".to_string()],
+            block_comments: vec![("/*".to_string(), "*/".to_string())],
+            nested: true,
+            quotes: vec![("\"".to_string(), "\"".to_string())],
+        }
+    }
+
+    fn lines(code: u32, comments: u32, blanks: u32) -> Lines {
+        Lines {
+            code,
+            comments,
+            blanks,
+        }
+    }
+
+    #[test]
+    fn nests_block_comments() {
+        let source = "/* outer\n/* inner */\nstill outer */\nfn main() {}\n";
+        assert_eq!(classify(source, &rust()), lines(1, 3, 0));
+
+        let flat = Syntax {
+            nested: false,
+            ..rust()
+        };
+        // Without nesting the first `*/` ends the comment.
+        let source = "/* a /* b */\nx */\n";
+        assert_eq!(classify(source, &flat), lines(1, 1, 0));
+    }
+
+    #[test]
+    fn ignores_comment_markers_in_strings() {
+        let source = "let url = \"https://example.com\";\nlet c = \"/*\";\n// comment\n\n";
+        assert_eq!(classify(source, &rust()), lines(2, 1, 1));
+
+        let source = "let s = \"first\n// inside the string\n/* too */\";\n// after\n";
+        assert_eq!(classify(source, &rust()), lines(3, 1, 0));
+
+        let source = "let s = \"a \\\" // still a string\";\n// comment\n";
+        assert_eq!(classify(source, &rust()), lines(1, 1, 0));
+    }
+
+    #[test]
+    fn skips_char_literals_but_not_lifetimes() {
+        let source = "let c = '\"';\n// comment\nlet q = '\\'';\nlet u = '\\u{1F600}';\n\
+                      fn f<'a>(x: &'a str) -> &'a str { x } // \"\n/* comment */\n";
+        assert_eq!(classify(source, &rust()), lines(4, 2, 0));
+    }
+}
@@ -1,18 +1,36 @@
-use app::{get_branches, get_log, get_log_tree, get_status, get_tree};
-use clap::{App, Arg};
-use stats::get_stats;
-use std::io;
+use app::{get_git, get_git_state};
+use clap::{App, Arg, ArgMatches};
+use pstat::authors::{self, AuthorSort};
+use pstat::backend::{Git2Backend, GitBackend};
+use pstat::badge::{self, Badge, Style};
+use pstat::branches::{self, BranchSort};
+use pstat::churn::{self, HotspotSort};
+use pstat::dates;
+use pstat::export::{self, SortBy, TableOptions};
+use pstat::git::{self, Range};
+use pstat::history::{self, Sampling};
+use pstat::html;
+use pstat::languages::Languages;
+use pstat::ownership;
+use pstat::report::Report;
+use pstat::snapshot::{self, Diff};
+use pstat::stats::{get_percentages, get_stats};
+use pstat::tree;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
 use tui::style::Color;
+use tui::widgets::{ListState, TableState};
 
 mod app;
 mod ui;
 
-mod stats;
-use crate::stats::get_percentages;
-
 mod terminal;
 use crate::terminal::setup_terminal;
-use crate::terminal::TApp;
+use crate::terminal::{GitPanel, Pending, TApp};
 
 fn main() -> Result<(), io::Error> {
     let matches = App::new("Pstat")
@@ -24,21 +42,238 @@ fn main() -> Result<(), io::Error> {
                 .short('p')
                 .long("path")
                 .takes_value(true)
+                .global(true)
                 .help("Path to project directory (Defaults to current path)"),
         )
         .arg(
             Arg::with_name("ignore")
                 .short('i')
                 .long("ignore")
-                .help("Use .gitignore if exists"),
+                .global(true)
+                .help("Skip files ignored by git (.gitignore, .git/info/exclude, global excludes)"),
         )
         .arg(
             Arg::with_name("color")
                 .short('c')
                 .long("color")
                 .takes_value(true)
+                .global(true)
                 .help("Color scheme to use (Refer to colors supported by tui::style::Color)"),
         )
+        .arg(
+            Arg::with_name("threads")
+                .short('t')
+                .long("threads")
+                .takes_value(true)
+                .global(true)
+                .help("Number of threads used to scan the project (Defaults to one per CPU)"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .short('f')
+                .long("format")
+                .takes_value(true)
+                .possible_values(["tui", "json", "csv", "markdown"])
+                .global(true)
+                .help(
+                    "Output format, anything but tui prints to stdout instead of opening the TUI",
+                ),
+        )
+        .arg(
+            Arg::with_name("sort")
+                .short('s')
+                .long("sort")
+                .takes_value(true)
+                .possible_values(SortBy::NAMES)
+                .help("Column to sort the csv and markdown tables by (Defaults to bytes)"),
+        )
+        .arg(
+            Arg::with_name("totals")
+                .long("totals")
+                .help("End the csv and markdown tables with a totals row"),
+        )
+        .arg(
+            Arg::with_name("rev")
+                .short('r')
+                .long("rev")
+                .takes_value(true)
+                .value_name("RANGE")
+                .help(
+                    "Compare the stats of two git revisions, e.g. main..feature or main...feature",
+                ),
+        )
+        .arg(
+            Arg::with_name("baseline")
+                .short('b')
+                .long("baseline")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Snapshot to show per-language line deltas against in the File Stats table"),
+        )
+        .subcommand(
+            App::new("report")
+                .about("Write a self-contained report of the project")
+                .arg(
+                    Arg::with_name("html")
+                        .long("html")
+                        .takes_value(true)
+                        .required(true)
+                        .value_name("FILE")
+                        .help("HTML file to write, with the contents of the Home and Git tabs except ownership"),
+                ),
+        )
+        .subcommand(
+            App::new("badge")
+                .about("Render a shields-style SVG badge")
+                .arg(
+                    Arg::with_name("metric")
+                        .required(true)
+                        .possible_values(["lines", "files", "language", "commits"])
+                        .help("What the badge shows, language is the top language with its share"),
+                )
+                .arg(
+                    Arg::with_name("label")
+                        .long("label")
+                        .takes_value(true)
+                        .help("Text on the left of the badge (Defaults to the metric)"),
+                )
+                .arg(
+                    Arg::with_name("style")
+                        .long("style")
+                        .takes_value(true)
+                        .possible_values(Style::NAMES)
+                        .help("Badge style (Defaults to flat)"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short('o')
+                        .long("output")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help("SVG file to write (Defaults to stdout)"),
+                ),
+        )
+        .subcommand(
+            App::new("history")
+                .about("Show lines of code over the first-parent git history")
+                .arg(
+                    Arg::with_name("sample")
+                        .long("sample")
+                        .takes_value(true)
+                        .possible_values(Sampling::NAMES)
+                        .help("Which commits to count: the last of each day or week, or tags on that history (Defaults to week)"),
+                ),
+        )
+        .subcommand(
+            App::new("authors")
+                .about("Show commits and changed lines per author, merging identities with .mailmap")
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .takes_value(true)
+                        .possible_values(AuthorSort::NAMES)
+                        .help("Column to sort by (Defaults to commits)"),
+                )
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .takes_value(true)
+                        .value_name("DATE")
+                        .help("Only count commits from this day on, as YYYY-MM-DD"),
+                )
+                .arg(
+                    Arg::with_name("until")
+                        .long("until")
+                        .takes_value(true)
+                        .value_name("DATE")
+                        .help("Only count commits up to and including this day, as YYYY-MM-DD"),
+                ),
+        )
+        .subcommand(
+            App::new("branches")
+                .about("Show local and remote-tracking branches with their upstream, last commit and whether they are merged")
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .takes_value(true)
+                        .possible_values(BranchSort::NAMES)
+                        .help("Sort by name or by last commit, newest first (Defaults to name)"),
+                ),
+        )
+        .subcommand(
+            App::new("hotspots")
+                .about("Rank files by how often they changed times their size")
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .takes_value(true)
+                        .possible_values(HotspotSort::NAMES)
+                        .help("Column to sort by (Defaults to score)"),
+                )
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .takes_value(true)
+                        .value_name("DATE")
+                        .help("Only count commits from this day on, as YYYY-MM-DD"),
+                )
+                .arg(
+                    Arg::with_name("until")
+                        .long("until")
+                        .takes_value(true)
+                        .value_name("DATE")
+                        .help("Only count commits up to and including this day, as YYYY-MM-DD"),
+                )
+                .arg(
+                    Arg::with_name("top")
+                        .short('n')
+                        .long("top")
+                        .takes_value(true)
+                        .value_name("N")
+                        .help("Number of files to show, 0 for all (Defaults to 20)"),
+                ),
+        )
+        .subcommand(
+            App::new("ownership")
+                .about("Show who wrote the committed lines of each directory, using git blame (not part of --format json or the HTML report)")
+                .arg(
+                    Arg::with_name("depth")
+                        .long("depth")
+                        .takes_value(true)
+                        .value_name("N")
+                        .help("How many directory levels to show (Defaults to 1)"),
+                )
+                .arg(
+                    Arg::with_name("files")
+                        .long("files")
+                        .help("Show every file instead of directories"),
+                ),
+        )
+        .subcommand(
+            App::new("snapshot")
+                .about("Save scan results to compare them later")
+                .subcommand_required(true)
+                .subcommand(
+                    App::new("save")
+                        .about("Write the scan results to a JSON file")
+                        .arg(Arg::with_name("file").required(true).value_name("FILE")),
+                ),
+        )
+        .subcommand(
+            App::new("diff")
+                .about("Show per-language changes between two snapshots")
+                .arg(
+                    Arg::with_name("old")
+                        .required(true)
+                        .value_name("OLD")
+                        .help("Baseline snapshot"),
+                )
+                .arg(
+                    Arg::with_name("new")
+                        .value_name("NEW")
+                        .help("Snapshot to compare (Defaults to scanning the project)"),
+                ),
+        )
         .get_matches();
 
     let path = matches.value_of("path").unwrap_or(".");
@@ -64,42 +299,331 @@ fn main() -> Result<(), io::Error> {
         _ => Color::White,
     };
 
-    let mut ignore = matches.occurrences_of("ignore") > 0;
+    let threads = match matches.value_of("threads").unwrap_or("0").parse::<usize>() {
+        Ok(threads) => threads,
+        Err(_) => fail("The number of threads must be a non-negative integer."),
+    };
+
+    let ignore = matches.occurrences_of("ignore") > 0;
+
+    let languages = match Languages::load() {
+        Ok(languages) => languages,
+        Err(e) => fail(format!("Invalid language definitions: {}", e)),
+    };
+
+    let load = |file: &str| match snapshot::load(Path::new(file)) {
+        Ok(report) => report,
+        Err(e) => fail(format!("Invalid snapshot: {}", e)),
+    };
+    let format = matches.value_of("format").unwrap_or("tui");
+
+    if let Some(args) = matches.subcommand_matches("diff") {
+        let old = load(args.value_of("old").unwrap());
+        let new = match args.value_of("new") {
+            Some(file) => load(file),
+            None => Report::new(path, &get_stats(path, ignore, &languages, threads), 5, None),
+        };
+
+        return print_diff(&snapshot::diff(&old, &new), format);
+    }
+
+    if let Some(args) = matches.subcommand_matches("history") {
+        let sampling = args
+            .value_of("sample")
+            .map_or(Sampling::default(), |sample| sample.parse().unwrap());
+        let history = match history::history(path, sampling, &languages) {
+            Ok(history) => history,
+            Err(e) => fail(format!("Couldn't read the history: {}", e)),
+        };
+
+        let mut stdout = io::stdout().lock();
+        return match format {
+            "json" => {
+                serde_json::to_writer_pretty(&mut stdout, &history)?;
+                writeln!(stdout)
+            }
+            "csv" => write!(stdout, "{}", history::csv(&history)),
+            "markdown" => write!(stdout, "{}", history::markdown(&history)),
+            _ => write!(stdout, "{}", history::text(&history)),
+        };
+    }
+
+    if let Some(args) = matches.subcommand_matches("authors") {
+        let (since, until) = date_range(args);
+        let sort = args
+            .value_of("sort")
+            .map_or(AuthorSort::default(), |sort| sort.parse().unwrap());
+
+        let contributions = match authors::contributions(path) {
+            Ok(contributions) => contributions,
+            Err(e) => fail(format!("Couldn't read the history: {}", e)),
+        };
+        let authors = authors::authors(&contributions, since, until, sort);
+
+        let mut stdout = io::stdout().lock();
+        return match format {
+            "json" => {
+                serde_json::to_writer_pretty(&mut stdout, &authors)?;
+                writeln!(stdout)
+            }
+            "csv" => write!(stdout, "{}", authors::csv(&authors)),
+            "markdown" => write!(stdout, "{}", authors::markdown(&authors)),
+            _ => write!(stdout, "{}", authors::text(&authors)),
+        };
+    }
+
+    if let Some(args) = matches.subcommand_matches("branches") {
+        let sort = args
+            .value_of("sort")
+            .map_or(BranchSort::default(), |sort| sort.parse().unwrap());
+
+        let git = match Git2Backend::open(path) {
+            Ok(git) => git,
+            Err(e) => fail(format!("Not a git repository: {}", e)),
+        };
+        let branches = match git.branches() {
+            Ok(list) => branches::report(&list, git.default_branch().as_deref(), sort),
+            Err(e) => fail(format!("Couldn't read the branches: {}", e)),
+        };
+
+        let mut stdout = io::stdout().lock();
+        return match format {
+            "json" => {
+                serde_json::to_writer_pretty(&mut stdout, &branches)?;
+                writeln!(stdout)
+            }
+            "csv" => write!(stdout, "{}", branches::csv(&branches)),
+            "markdown" => write!(stdout, "{}", branches::markdown(&branches)),
+            _ => write!(stdout, "{}", branches::text(&branches)),
+        };
+    }
+
+    if let Some(range) = matches.value_of("rev") {
+        let scans = Range::parse(range).and_then(|range| git::scan_range(path, &range, &languages));
+        let (old, new) = match scans {
+            Ok(scans) => scans,
+            Err(e) => fail(format!("Couldn't compare revisions: {}", e)),
+        };
+        let old = Report::new(path, &old, 0, None);
+        let new = Report::new(path, &new, 0, None);
+        return print_diff(&snapshot::diff(&old, &new), format);
+    }
+
+    let baseline = matches.value_of("baseline").map(load);
+
+    let scan = get_stats(path, ignore, &languages, threads);
+    let backend = Git2Backend::open(path);
+
+    if let Some(args) = matches.subcommand_matches("snapshot") {
+        let file = args
+            .subcommand_matches("save")
+            .unwrap()
+            .value_of("file")
+            .unwrap();
+        let report = Report::new(
+            path,
+            &scan,
+            5,
+            backend.as_ref().ok().map(|git| get_git(git, 5)),
+        );
 
-    if ignore && !(path.eq(".") || path.eq("./")) {
-        println!("Cannot use -i with another directory other than the current one.");
-        println!("Either use the current working directory or omit the -i flag.");
+        match snapshot::save(&report, Path::new(file)) {
+            Ok(()) => println!("Saved snapshot to {}", file),
+            Err(e) => fail(e),
+        }
         return Ok(());
     }
 
-    let (file_stats, proj_size, times) = get_stats(path, &mut ignore);
-    let lang_stats = get_percentages(&file_stats, proj_size);
+    if let Some(args) = matches.subcommand_matches("hotspots") {
+        let (since, until) = date_range(args);
+        let top = match args.value_of("top").unwrap_or("20").parse::<usize>() {
+            Ok(top) => top,
+            Err(_) => fail("The number of files must be a non-negative integer."),
+        };
+        let sort = args
+            .value_of("sort")
+            .map_or(HotspotSort::default(), |sort| sort.parse().unwrap());
+
+        let changes = match churn::changes(path) {
+            Ok(changes) => changes,
+            Err(e) => fail(format!("Couldn't read the history: {}", e)),
+        };
+        let mut hotspots = churn::hotspots(&changes, &scan, since, until, sort);
+        if top > 0 {
+            hotspots.truncate(top);
+        }
+
+        let mut stdout = io::stdout().lock();
+        return match format {
+            "json" => {
+                serde_json::to_writer_pretty(&mut stdout, &hotspots)?;
+                writeln!(stdout)
+            }
+            "csv" => write!(stdout, "{}", churn::csv(&hotspots)),
+            "markdown" => write!(stdout, "{}", churn::markdown(&hotspots)),
+            _ => write!(stdout, "{}", churn::text(&hotspots)),
+        };
+    }
+
+    if let Some(args) = matches.subcommand_matches("ownership") {
+        let depth = match args.value_of("depth").unwrap_or("1").parse::<usize>() {
+            Ok(depth) => depth,
+            Err(_) => fail("The depth must be a non-negative integer."),
+        };
+        let ownership = match ownership::blame(path, &scan.files) {
+            Ok(ownership) => ownership,
+            Err(e) => fail(format!("Couldn't blame files: {}", e)),
+        };
+        let reports = ownership::report(&ownership, &scan, depth, args.is_present("files"));
 
-    let mut count_time: Vec<(&String, &u64)> = times.iter().collect();
-    count_time.sort_by(|a, b| a.1.cmp(b.1));
+        let mut stdout = io::stdout().lock();
+        return match format {
+            "json" => {
+                serde_json::to_writer_pretty(&mut stdout, &reports)?;
+                writeln!(stdout)
+            }
+            "csv" => write!(stdout, "{}", ownership::csv(&reports)),
+            "markdown" => write!(stdout, "{}", ownership::markdown(&reports)),
+            _ => write!(stdout, "{}", ownership::text(&reports)),
+        };
+    }
 
-    let mut file_time: Vec<String> = Vec::new();
-    for i in count_time.iter().take(5) {
-        file_time.push(i.0.to_string());
+    if let Some(report) = matches.subcommand_matches("report") {
+        let out = report.value_of("html").unwrap();
+        let tree = tree::build(path, &scan);
+        let report = Report::new(
+            path,
+            &scan,
+            5,
+            backend.as_ref().ok().map(|git| get_git(git, 20)),
+        );
+
+        fs::write(out, html::render(&report, &tree))?;
+        println!("Wrote report to {}", out);
+        return Ok(());
     }
 
-    let branches = get_branches(path);
-    let log_tree = get_log_tree(path);
-    let log = get_log(path);
-    let status = get_status(path);
-    let tree = get_tree(ignore, path);
+    if let Some(args) = matches.subcommand_matches("badge") {
+        let badge = match args.value_of("metric").unwrap() {
+            "lines" => Some(Badge::lines(&scan)),
+            "files" => Some(Badge::files(&scan)),
+            "language" => Badge::language(&scan),
+            _ => match backend
+                .as_ref()
+                .ok()
+                .and_then(|git| git.commit_count().ok())
+            {
+                Some(count) => Some(Badge::commits(count)),
+                None => fail("Not a git repository. No commits found."),
+            },
+        };
+        let mut badge = match badge {
+            Some(badge) => badge,
+            None => fail("No programming or markup languages found."),
+        };
+
+        if let Some(label) = args.value_of("label") {
+            badge.label = label.to_string();
+        }
+        if let Some(name) = matches.value_of("color") {
+            badge.color = match badge::color(name) {
+                Some(hex) => hex.to_string(),
+                None => fail(format!("Unknown color: {}", name)),
+            };
+        }
+        let style = args
+            .value_of("style")
+            .map_or(Style::default(), |style| style.parse().unwrap());
+
+        let svg = badge.render(style);
+        match args.value_of("output") {
+            Some(out) => fs::write(out, svg)?,
+            None => print!("{}", svg),
+        }
+        return Ok(());
+    }
+
+    if format != "tui" {
+        let options = TableOptions {
+            sort: matches
+                .value_of("sort")
+                .map_or(SortBy::default(), |sort| sort.parse().unwrap()),
+            totals: matches.is_present("totals"),
+        };
+        let git = if format == "json" {
+            backend.as_ref().ok().map(|git| get_git(git, 5))
+        } else {
+            None
+        };
+        let report = Report::new(path, &scan, 5, git);
+
+        let mut stdout = io::stdout().lock();
+        match format {
+            "json" => {
+                serde_json::to_writer_pretty(&mut stdout, &report)?;
+                writeln!(stdout)?;
+            }
+            "csv" => write!(stdout, "{}", export::csv(&report, options))?,
+            _ => write!(stdout, "{}", export::markdown(&report, options))?,
+        }
+        return Ok(());
+    }
+
+    let lang_stats = get_percentages(&scan.file_counts);
+    let tree = tree::build(path, &scan);
+    let file_time = scan.recent(5);
+
+    let git = backend
+        .as_ref()
+        .map_err(Clone::clone)
+        .and_then(get_git_state);
+    let backend = backend
+        .ok()
+        .map(|backend| Box::new(backend) as Box<dyn GitBackend>);
+
+    let mut tree_state = ListState::default();
+    tree_state.select(Some(0));
 
     let mut app = TApp {
-        scroll: (0, 0),
-        status_scroll: (0, 0),
+        status_state: ListState::default(),
+        expanded: HashSet::from([tree.path.clone()]),
         tree,
+        tree_state,
+        filter: None,
+        baseline,
+        languages,
+        trends: None,
+        contributions: None,
+        activity: None,
+        author_sort: AuthorSort::default(),
+        author_range: 0,
+        authors_state: TableState::default(),
+        activity_author: 0,
+        ownership: None,
+        show_ownership: false,
+        pending: Pending::default(),
+        changes: None,
+        hotspot_sort: HotspotSort::default(),
+        hotspot_range: 0,
+        hotspots_state: TableState::default(),
+        punchcard: false,
         path: String::from(path),
-        file_stats,
+        file_stats: scan.file_counts.clone(),
+        binaries: scan.binaries,
+        scan,
         lang_stats,
-        branches,
-        log,
-        log_tree,
-        status,
+        git,
+        backend,
+        git_focus: GitPanel::Status,
+        branch_sort: BranchSort::default(),
+        branches_state: TableState::default(),
+        log_state: ListState::default(),
+        log_search: String::new(),
+        search: None,
+        search_error: None,
+        detail: None,
+        detail_file: 0,
+        detail_scroll: 0,
         file_time,
         app_color,
         tab: 0,
@@ -108,3 +632,39 @@ fn main() -> Result<(), io::Error> {
 
     setup_terminal(&mut app)
 }
+
+/// Prints `message` to stderr and exits with a failure status.
+fn fail(message: impl Display) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+/// Reads the `--since` and `--until` dates of `args` as a range of seconds
+/// since the epoch, with `until` exclusive so that its day is included.
+/// Exits when a date is invalid.
+fn date_range(args: &ArgMatches) -> (Option<i64>, Option<i64>) {
+    let mut range = [None, None];
+    for (bound, name) in range.iter_mut().zip(["since", "until"]) {
+        if let Some(date) = args.value_of(name) {
+            match dates::parse(date) {
+                Some(time) => *bound = Some(time),
+                None => fail(format!("Invalid date {}, expected YYYY-MM-DD.", date)),
+            }
+        }
+    }
+    let [since, until] = range;
+    (since, until.map(|until| until + dates::DAY))
+}
+
+fn print_diff(diff: &Diff, format: &str) -> Result<(), io::Error> {
+    let mut stdout = io::stdout().lock();
+    match format {
+        "json" => {
+            serde_json::to_writer_pretty(&mut stdout, diff)?;
+            writeln!(stdout)
+        }
+        "csv" => write!(stdout, "{}", snapshot::csv(diff)),
+        "markdown" => write!(stdout, "{}", snapshot::markdown(diff)),
+        _ => write!(stdout, "{}", snapshot::text(diff)),
+    }
+}
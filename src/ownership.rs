@@ -0,0 +1,297 @@
+//! Who wrote the committed lines of each file, from `git blame`, and how many
+//! people the code depends on.
+
+use crate::stats::{FileStat, Scan};
+use crate::{export, git};
+use git2::{BlameOptions, Repository};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Version of the blame cache, bumped when the way lines are counted changes.
+const CACHE_VERSION: u32 = 1;
+
+/// Lines of a file or directory last changed by one author.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Owner {
+    pub name: String,
+    pub email: String,
+    pub lines: u32,
+}
+
+/// Blamed owners of already seen file versions, so that later runs only blame
+/// changed files. Owners by path relative to the repository and blob id.
+type Cache = git::Cache<HashMap<String, Vec<Owner>>>;
+
+/// The owners of every committed text file of a project.
+#[derive(Clone, Debug, Default)]
+pub struct Ownership {
+    /// Owners by path relative to the project root, most lines first.
+    pub files: HashMap<PathBuf, Vec<Owner>>,
+}
+
+impl Ownership {
+    /// Owners of the file `path`, or of all files under the directory `path`,
+    /// relative to the project root. Identities with the same email are
+    /// merged, and the owners with the most lines come first.
+    pub fn owners(&self, path: &Path) -> Vec<Owner> {
+        let mut owners: HashMap<String, Owner> = HashMap::new();
+        for (file, file_owners) in &self.files {
+            if !file.starts_with(path) {
+                continue;
+            }
+            for owner in file_owners {
+                owners
+                    .entry(owner.email.to_lowercase())
+                    .and_modify(|o| o.lines += owner.lines)
+                    .or_insert_with(|| owner.clone());
+            }
+        }
+        sorted(owners.into_values().collect())
+    }
+}
+
+fn sorted(mut owners: Vec<Owner>) -> Vec<Owner> {
+    owners.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.name.cmp(&b.name)));
+    owners
+}
+
+/// The smallest number of authors that together own more than half of the
+/// lines, so the ones the code depends on most. 0 when there are no lines.
+pub fn bus_factor(owners: &[Owner]) -> u32 {
+    let total: u32 = owners.iter().map(|o| o.lines).sum();
+    let mut owned = 0;
+    for (i, owner) in sorted(owners.to_vec()).iter().enumerate() {
+        owned += owner.lines;
+        if owned * 2 > total {
+            return i as u32 + 1;
+        }
+    }
+    0
+}
+
+fn blame_file(repo: &Repository, path: &Path) -> Result<Vec<Owner>, git2::Error> {
+    let blame = repo.blame_file(path, Some(BlameOptions::new().use_mailmap(true)))?;
+    let mut owners: HashMap<String, Owner> = HashMap::new();
+    for hunk in blame.iter() {
+        let signature = match hunk.final_signature() {
+            Some(signature) => signature,
+            None => continue,
+        };
+        let email = String::from_utf8_lossy(signature.email_bytes()).into_owned();
+        owners
+            .entry(email.to_lowercase())
+            .or_insert_with(|| Owner {
+                name: String::from_utf8_lossy(signature.name_bytes()).into_owned(),
+                email,
+                lines: 0,
+            })
+            .lines += hunk.lines_in_hunk() as u32;
+    }
+    Ok(sorted(owners.into_values().collect()))
+}
+
+/// Blames the text files among `files` as they are committed in HEAD of the
+/// repository holding `path`. Files that aren't committed or can't be blamed
+/// are left out.
+pub fn blame(path: &str, files: &[FileStat]) -> Result<Ownership, String> {
+    let repo = Repository::discover(path).map_err(|e| e.message().to_string())?;
+    let prefix = git::prefix(&repo, path)?;
+    let tree = match repo.head().and_then(|head| head.peel_to_tree()) {
+        Ok(tree) => tree,
+        // No commits yet.
+        Err(_) => return Ok(Ownership::default()),
+    };
+    let mut cache = Cache::load(&repo, "ownership.json", CACHE_VERSION);
+
+    let mut ownership = Ownership::default();
+    for file in files.iter().filter(|file| !file.binary) {
+        let in_repo = prefix.join(&file.path);
+        let blob = match tree.get_path(&in_repo) {
+            Ok(entry) => entry.id(),
+            Err(_) => continue,
+        };
+        let key = format!("{}:{}", in_repo.to_string_lossy(), blob);
+        let owners = match cache.entries.get(&key) {
+            Some(owners) => owners.clone(),
+            None => {
+                let owners = match blame_file(&repo, &in_repo) {
+                    Ok(owners) => owners,
+                    Err(_) => continue,
+                };
+                cache.entries.insert(key, owners.clone());
+                owners
+            }
+        };
+        ownership.files.insert(file.path.clone(), owners);
+    }
+
+    cache.save();
+    Ok(ownership)
+}
+
+/// Owners of a file or directory, for exports.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OwnershipReport {
+    pub path: String,
+    pub lines: u32,
+    pub bus_factor: u32,
+    pub owners: Vec<Owner>,
+}
+
+impl OwnershipReport {
+    pub fn new(ownership: &Ownership, path: &Path) -> OwnershipReport {
+        let owners = ownership.owners(path);
+        OwnershipReport {
+            path: match path.as_os_str().is_empty() {
+                true => ".".to_string(),
+                false => path.to_string_lossy().into_owned(),
+            },
+            lines: owners.iter().map(|o| o.lines).sum(),
+            bus_factor: bus_factor(&owners),
+            owners,
+        }
+    }
+}
+
+/// The project and its directories down to `depth` levels, or every file
+/// when `files` is set, in path order.
+pub fn report(
+    ownership: &Ownership,
+    scan: &Scan,
+    depth: usize,
+    files: bool,
+) -> Vec<OwnershipReport> {
+    let mut paths: Vec<PathBuf> = match files {
+        true => ownership.files.keys().cloned().collect(),
+        false => scan
+            .dirs
+            .iter()
+            .filter(|dir| dir.components().count() <= depth)
+            .cloned()
+            .chain([PathBuf::new()])
+            .collect(),
+    };
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| OwnershipReport::new(ownership, path))
+        .filter(|report| report.lines > 0)
+        .collect()
+}
+
+fn rows(reports: &[OwnershipReport]) -> Vec<Vec<String>> {
+    let header = [
+        "Path",
+        "Lines",
+        "Authors",
+        "Bus factor",
+        "Top owner",
+        "Share",
+    ];
+    let mut rows = vec![header.iter().map(|h| h.to_string()).collect()];
+    for r in reports {
+        let top = r.owners.first();
+        rows.push(vec![
+            r.path.clone(),
+            r.lines.to_string(),
+            r.owners.len().to_string(),
+            r.bus_factor.to_string(),
+            top.map(|o| o.name.clone()).unwrap_or_default(),
+            top.map(|o| format!("{:.1}", o.lines as f64 * 100.0 / r.lines as f64))
+                .unwrap_or_default(),
+        ]);
+    }
+    rows
+}
+
+/// Renders the reports as a plain text table.
+pub fn text(reports: &[OwnershipReport]) -> String {
+    if reports.is_empty() {
+        return "No committed files found.\n".to_string();
+    }
+    export::text_rows(&rows(reports))
+}
+
+pub fn markdown(reports: &[OwnershipReport]) -> String {
+    export::markdown_rows(&rows(reports))
+}
+
+pub fn csv(reports: &[OwnershipReport]) -> String {
+    export::csv_rows(&rows(reports))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::DAY;
+    use crate::git::tests::{at, commit_as, repo};
+    use crate::languages::Languages;
+    use crate::stats::FileStat;
+    use std::fs;
+
+    fn owner(name: &str, lines: u32) -> Owner {
+        Owner {
+            name: name.to_string(),
+            email: format!("{}@example.com", name.to_lowercase()),
+            lines,
+        }
+    }
+
+    #[test]
+    fn computes_bus_factors() {
+        assert_eq!(bus_factor(&[]), 0);
+        assert_eq!(bus_factor(&[owner("Ada", 6), owner("Alan", 4)]), 1);
+        assert_eq!(bus_factor(&[owner("Ada", 5), owner("Alan", 5)]), 2);
+        let owners = [owner("Ada", 3), owner("Alan", 3), owner("Grace", 4)];
+        assert_eq!(bus_factor(&owners), 2);
+    }
+
+    #[test]
+    fn blames_committed_lines() {
+        let (root, repo) = repo("ownership");
+        commit_as(
+            &repo,
+            &[("src/a.rs", "a\nb\nc\n"), ("b.rs", "x\n")],
+            "first",
+            &at("Ada", 0),
+        );
+        commit_as(
+            &repo,
+            &[("src/a.rs", "a\nB\nc\nd\n"), ("b.rs", "x\n")],
+            "second",
+            &at("Alan", DAY),
+        );
+        // Not committed, so not blamed.
+        fs::write(root.join("new.rs"), "y\n").unwrap();
+
+        let languages = Languages::builtin();
+        let files = ["src/a.rs", "b.rs", "new.rs"]
+            .iter()
+            .map(|name| {
+                let contents = fs::read(root.join(name)).unwrap();
+                FileStat::from_contents(Path::new(name), contents, &languages)
+            })
+            .collect();
+        let scan = Scan::new(files, vec![PathBuf::from("src")]);
+
+        let ownership = blame(root.to_str().unwrap(), &scan.files).unwrap();
+        assert_eq!(ownership.files.len(), 2);
+        assert_eq!(
+            ownership.owners(Path::new("src")),
+            vec![owner("Ada", 2), owner("Alan", 2)]
+        );
+        assert_eq!(
+            ownership.owners(Path::new("")),
+            vec![owner("Ada", 3), owner("Alan", 2)]
+        );
+
+        let reports = report(&ownership, &scan, 1, false);
+        let paths: Vec<&str> = reports.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, [".", "src"]);
+        assert_eq!((reports[0].lines, reports[0].bus_factor), (5, 1));
+        assert_eq!(reports[1].bus_factor, 2);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,168 @@
+use crate::languages::Category;
+use crate::lines::Lines;
+use crate::stats::{get_percentages, Binaries, Scan};
+use serde::{Deserialize, Serialize};
+
+/// Version of the report schema. Bumped whenever a field is renamed, removed
+/// or changes meaning; new fields may be added without a bump.
+pub const VERSION: u32 = 1;
+
+/// Everything pstat knows about a project, as printed by `--format json`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Report {
+    pub version: u32,
+    /// The scanned directory, as given on the command line.
+    pub path: String,
+    pub totals: Totals,
+    /// Languages found in the project, largest first.
+    pub languages: Vec<LanguageReport>,
+    pub binaries: Binaries,
+    /// Names of the most recently modified files, newest first.
+    pub recent: Vec<String>,
+    /// Repository state, or `null` if the project isn't a git repository.
+    pub git: Option<Git>,
+}
+
+/// Sums over every file with a known language.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Totals {
+    pub files: u32,
+    #[serde(flatten)]
+    pub lines: Lines,
+    pub bytes: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LanguageReport {
+    pub name: String,
+    pub category: Category,
+    pub files: u32,
+    pub tests: u32,
+    pub minified: u32,
+    #[serde(flatten)]
+    pub lines: Lines,
+    pub bytes: u64,
+    /// Share of the language distribution, only set for programming and
+    /// markup languages.
+    pub percentage: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Git {
+    /// The checked out branch, `null` on a detached HEAD.
+    pub branch: Option<String>,
+    pub branches: Vec<String>,
+    /// Uncommitted changes, as listed by `git status --porcelain`.
+    pub status: Vec<Change>,
+    /// The latest commits, newest first.
+    pub log: Vec<Commit>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Change {
+    /// Two-letter index and worktree status, e.g. ` M` or `??`.
+    pub status: String,
+    pub path: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Commit {
+    pub id: String,
+    pub author: String,
+    pub email: String,
+    /// Author date in ISO 8601 format.
+    pub date: String,
+    pub summary: String,
+}
+
+impl Report {
+    pub fn new(path: &str, scan: &Scan, recent: usize, git: Option<Git>) -> Report {
+        let percentages = get_percentages(&scan.file_counts);
+        let mut totals = Totals::default();
+
+        let mut languages: Vec<LanguageReport> = scan
+            .file_counts
+            .iter()
+            .map(|(name, stats)| {
+                totals.files += stats.files;
+                totals.lines.add(&stats.lines);
+                totals.bytes += stats.bytes;
+
+                LanguageReport {
+                    name: name.clone(),
+                    category: stats.category,
+                    files: stats.files,
+                    tests: stats.tests,
+                    minified: stats.minified,
+                    lines: stats.lines,
+                    bytes: stats.bytes,
+                    percentage: percentages.get(name).copied(),
+                }
+            })
+            .collect();
+        languages.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+
+        Report {
+            version: VERSION,
+            path: path.to_string(),
+            totals,
+            languages,
+            binaries: scan.binaries,
+            recent: scan.recent(recent),
+            git,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::Variant;
+    use crate::stats::FileStat;
+    use std::path::PathBuf;
+
+    fn file(path: &str, language: &str, category: Category, code: u32, bytes: u64) -> FileStat {
+        FileStat {
+            path: PathBuf::from(path),
+            language: Some(language.to_string()),
+            category,
+            variant: Variant::Source,
+            lines: Lines {
+                code,
+                comments: 1,
+                blanks: 1,
+            },
+            bytes,
+            binary: false,
+            modified: bytes,
+        }
+    }
+
+    #[test]
+    fn serializes_stable_schema() {
+        let scan = Scan::new(
+            vec![
+                file("src/main.rs", "Rust", Category::Programming, 10, 300),
+                file("src/lib.rs", "Rust", Category::Programming, 5, 100),
+                file("Cargo.toml", "TOML", Category::Config, 8, 200),
+            ],
+            vec![PathBuf::from("src")],
+        );
+        let report = Report::new(".", &scan, 2, None);
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["version"], VERSION);
+        assert_eq!(json["totals"]["files"], 3);
+        assert_eq!(json["totals"]["code"], 23);
+        assert_eq!(json["totals"]["bytes"], 600);
+        assert_eq!(json["languages"][0]["name"], "Rust");
+        assert_eq!(json["languages"][0]["category"], "programming");
+        assert_eq!(json["languages"][0]["percentage"], 100.0);
+        assert_eq!(json["languages"][1]["percentage"], serde_json::Value::Null);
+        assert_eq!(json["recent"], serde_json::json!(["lib.rs", "Cargo.toml"]));
+        assert_eq!(json["git"], serde_json::Value::Null);
+
+        let parsed: Report = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.languages.len(), 2);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::export;
+use crate::report::{self, LanguageReport, Report};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Writes `report` to `file` as JSON. Snapshots are the same document as
+/// `--format json`, so archived reports can serve as baselines too.
+pub fn save(report: &Report, file: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(file, json + "\n").map_err(|e| format!("Couldn't write {}: {}", file.display(), e))
+}
+
+/// Reads a snapshot written by `save` or `--format json`.
+pub fn load(file: &Path) -> Result<Report, String> {
+    let contents =
+        fs::read_to_string(file).map_err(|e| format!("Couldn't read {}: {}", file.display(), e))?;
+    let report: Report =
+        serde_json::from_str(&contents).map_err(|e| format!("{}: {}", file.display(), e))?;
+
+    if report.version > report::VERSION {
+        return Err(format!(
+            "{} was written by a newer pstat (schema version {})",
+            file.display(),
+            report.version
+        ));
+    }
+    Ok(report)
+}
+
+/// Signed change of a language's numbers between two snapshots.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delta {
+    pub files: i64,
+    pub code: i64,
+    pub comments: i64,
+    pub blanks: i64,
+    pub bytes: i64,
+}
+
+impl Delta {
+    fn between(old: Option<&LanguageReport>, new: Option<&LanguageReport>) -> Delta {
+        let numbers = |lang: Option<&LanguageReport>| match lang {
+            Some(l) => [
+                l.files as i64,
+                l.lines.code as i64,
+                l.lines.comments as i64,
+                l.lines.blanks as i64,
+                l.bytes as i64,
+            ],
+            None => [0; 5],
+        };
+        let (old, new) = (numbers(old), numbers(new));
+
+        Delta {
+            files: new[0] - old[0],
+            code: new[1] - old[1],
+            comments: new[2] - old[2],
+            blanks: new[3] - old[3],
+            bytes: new[4] - old[4],
+        }
+    }
+
+    /// Change in code, comment and blank lines together.
+    pub fn lines(&self) -> i64 {
+        self.code + self.comments + self.blanks
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Delta::default()
+    }
+
+    fn add(&mut self, other: &Delta) {
+        self.files += other.files;
+        self.code += other.code;
+        self.comments += other.comments;
+        self.blanks += other.blanks;
+        self.bytes += other.bytes;
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LanguageDelta {
+    pub name: String,
+    #[serde(flatten)]
+    pub delta: Delta,
+}
+
+/// Per-language changes between two snapshots, as printed by `pstat diff`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Diff {
+    pub version: u32,
+    /// Languages whose numbers changed, biggest change in lines first.
+    pub languages: Vec<LanguageDelta>,
+    pub totals: Delta,
+}
+
+pub fn diff(old: &Report, new: &Report) -> Diff {
+    let mut names: BTreeMap<&str, (Option<&LanguageReport>, Option<&LanguageReport>)> =
+        BTreeMap::new();
+    for lang in &old.languages {
+        names.entry(&lang.name).or_default().0 = Some(lang);
+    }
+    for lang in &new.languages {
+        names.entry(&lang.name).or_default().1 = Some(lang);
+    }
+
+    let mut totals = Delta::default();
+    let mut languages: Vec<LanguageDelta> = names
+        .into_iter()
+        .map(|(name, (old, new))| LanguageDelta {
+            name: name.to_string(),
+            delta: Delta::between(old, new),
+        })
+        .filter(|lang| !lang.delta.is_zero())
+        .inspect(|lang| totals.add(&lang.delta))
+        .collect();
+    languages.sort_by_key(|lang| std::cmp::Reverse(lang.delta.lines().abs()));
+
+    Diff {
+        version: report::VERSION,
+        languages,
+        totals,
+    }
+}
+
+/// Formats a change with an explicit sign, e.g. `+12`, `-3` or `0`.
+pub fn signed(n: i64) -> String {
+    if n > 0 {
+        format!("+{}", n)
+    } else {
+        n.to_string()
+    }
+}
+
+fn rows(diff: &Diff) -> Vec<Vec<String>> {
+    let header = [
+        "Language", "Files", "Code", "Comments", "Blanks", "Lines", "Bytes",
+    ];
+    let mut rows = vec![header.iter().map(|h| h.to_string()).collect()];
+
+    let totals = LanguageDelta {
+        name: "Total".to_string(),
+        delta: diff.totals,
+    };
+    for lang in diff.languages.iter().chain([&totals]) {
+        let d = lang.delta;
+        rows.push(vec![
+            lang.name.clone(),
+            signed(d.files),
+            signed(d.code),
+            signed(d.comments),
+            signed(d.blanks),
+            signed(d.lines()),
+            signed(d.bytes),
+        ]);
+    }
+    rows
+}
+
+/// Renders the diff as a plain text table.
+pub fn text(diff: &Diff) -> String {
+    if diff.languages.is_empty() {
+        return "No changes.\n".to_string();
+    }
+    export::text_rows(&rows(diff))
+}
+
+/// Renders the diff as a Markdown table, e.g. for a pull request comment.
+pub fn markdown(diff: &Diff) -> String {
+    export::markdown_rows(&rows(diff))
+}
+
+pub fn csv(diff: &Diff) -> String {
+    export::csv_rows(&rows(diff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::Category;
+    use crate::lines::Lines;
+    use crate::report::Totals;
+    use crate::stats::Binaries;
+
+    fn report(languages: &[(&str, u32, u32, u64)]) -> Report {
+        Report {
+            version: report::VERSION,
+            path: ".".to_string(),
+            totals: Totals::default(),
+            languages: languages
+                .iter()
+                .map(|&(name, files, code, bytes)| LanguageReport {
+                    name: name.to_string(),
+                    category: Category::Programming,
+                    files,
+                    tests: 0,
+                    minified: 0,
+                    lines: Lines {
+                        code,
+                        comments: 0,
+                        blanks: 0,
+                    },
+                    bytes,
+                    percentage: None,
+                })
+                .collect(),
+            binaries: Binaries::default(),
+            recent: Vec::new(),
+            git: None,
+        }
+    }
+
+    #[test]
+    fn diffs_languages() {
+        let old = report(&[("Rust", 3, 100, 4000), ("Go", 1, 10, 200), ("C", 1, 5, 50)]);
+        let new = report(&[("Rust", 4, 160, 5000), ("C", 1, 5, 50), ("Zig", 1, 20, 300)]);
+        let diff = diff(&old, &new);
+
+        let names: Vec<&str> = diff.languages.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, ["Rust", "Zig", "Go"]);
+        assert_eq!(diff.languages[0].delta.files, 1);
+        assert_eq!(diff.languages[0].delta.code, 60);
+        assert_eq!(diff.languages[2].delta.bytes, -200);
+        assert_eq!(diff.totals.lines(), 70);
+
+        assert_eq!(
+            text(&diff),
+            "Language  Files  Code  Comments  Blanks  Lines  Bytes\n\
+             Rust         +1   +60         0       0    +60  +1000\n\
+             Zig          +1   +20         0       0    +20   +300\n\
+             Go           -1   -10         0       0    -10   -200\n\
+             Total        +1   +70         0       0    +70  +1100\n"
+        );
+    }
+
+    #[test]
+    fn round_trips_snapshots() {
+        let file = std::env::temp_dir().join(format!("pstat-snapshot-{}.json", std::process::id()));
+        let old = report(&[("Rust", 3, 100, 4000)]);
+        save(&old, &file).unwrap();
+
+        let loaded = load(&file).unwrap();
+        assert!(diff(&old, &loaded).languages.is_empty());
+        assert_eq!(text(&diff(&old, &loaded)), "No changes.\n");
+
+        let mut newer = old.clone();
+        newer.version = report::VERSION + 1;
+        save(&newer, &file).unwrap();
+        assert!(load(&file).unwrap_err().contains("newer pstat"));
+        fs::remove_file(file).unwrap();
+    }
+}
@@ -1,133 +1,397 @@
-extern crate walkdir;
+use crate::detect;
+use crate::languages::{self, Category, Languages, Variant};
+use crate::lines::{self, Lines, Syntax};
+use ignore::{DirEntry, ParallelVisitor, ParallelVisitorBuilder, WalkBuilder, WalkState};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::ffi::OsStr;
-use std::fs::{self};
-use std::path::Path;
-use std::process::Command;
-use std::time::SystemTime;
-use walkdir::WalkDir;
-
-pub fn count_lines(file: &Path) -> u32 {
-    let contents = fs::read_to_string(file);
-    if let Ok(lines) = contents {
-        lines.lines().count().try_into().unwrap()
-    } else {
-        0
-    }
-}
-
-pub fn get_stats(
-    path: &str,
-    ignore: &mut bool,
-) -> (HashMap<String, (u32, u32, u64)>, u64, HashMap<String, u64>) {
-    let mut file_counts: HashMap<String, (u32, u32, u64)> = HashMap::new();
-    let mut times: HashMap<String, u64> = HashMap::new();
-    let mut proj_size: u64 = 0;
-
-    let ext_map = HashMap::from([
-        ("rs", String::from(" Rust")),
-        ("r", String::from("   R")),
-        ("c", String::from("  C")),
-        ("cs", String::from("  C#")),
-        ("cpp", String::from("  C++")),
-        ("java", String::from(" Java")),
-        ("scala", String::from("Scala")),
-        ("erl", String::from("Erlang")),
-        ("html", String::from(" HTML")),
-        ("css", String::from(" CSS")),
-        ("ejs", String::from(" EJS")),
-        ("rb", String::from(" Ruby")),
-        ("php", String::from(" PHP")),
-        ("js", String::from("JavaScript")),
-        ("ts", String::from("TypeScript")),
-        ("py", String::from("Python")),
-        ("go", String::from("  Go")),
-        ("cu", String::from(" CUDA")),
-        ("bash", String::from(" Bash")),
-    ]);
-
-    let ls = Command::new("git")
-        .arg("ls-files")
-        .output()
-        .expect("Git ls-files failed");
-    let ls = String::from_utf8(ls.stdout).expect("Can't convert git ls-files to string");
-    let git_ls: Vec<&str> = ls.split("\n").collect();
-
-    for file in WalkDir::new(path).into_iter().filter_map(|file| file.ok()) {
-        if file.metadata().unwrap().is_file() {
-            if *ignore {
-                let path2 = file
-                    .path()
-                    .strip_prefix("./")
-                    .expect("Coudn't strip prefix")
-                    .to_str()
-                    .unwrap();
-                
-                if cfg!(windows) {
-                    let path2 = &path2.replace("\\", "/")[..];
-                    if !git_ls.contains(&path2) {
-                        continue;
-                    }
-                } else {
-                    if !git_ls.contains(&path2) {
-                        continue;
-                    }
-                };
-            }
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LangStats {
+    pub category: Category,
+    pub files: u32,
+    pub tests: u32,
+    pub minified: u32,
+    pub lines: Lines,
+    pub bytes: u64,
+}
+
+/// Binary files such as images, fonts and archives, which have no lines.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Binaries {
+    pub files: u32,
+    pub bytes: u64,
+}
+
+/// Counts the lines of `file`, or returns `None` if it turns out to be binary.
+pub fn count_lines(file: &Path, syntax: &Syntax) -> io::Result<Option<Lines>> {
+    let bytes = fs::read(file)?;
+    if detect::is_binary(&bytes) {
+        return Ok(None);
+    }
+    Ok(Some(lines::classify(&detect::decode(bytes), syntax)))
+}
+
+fn looks_binary(file: &Path) -> bool {
+    detect::read_bytes(file, detect::SNIFF_SIZE).is_ok_and(|head| detect::is_binary(&head))
+}
+
+/// A single file of the project, with its path relative to the scanned directory.
+#[derive(Clone, Debug)]
+pub struct FileStat {
+    pub path: PathBuf,
+    pub language: Option<String>,
+    pub category: Category,
+    pub variant: Variant,
+    pub lines: Lines,
+    pub bytes: u64,
+    pub binary: bool,
+    /// Seconds since the file was last modified.
+    pub modified: u64,
+}
+
+impl FileStat {
+    /// Reads a file found by the walk, or returns `None` if it can't be read,
+    /// for instance because it was deleted in the meantime.
+    fn read(
+        file: &Path,
+        rel: &Path,
+        metadata: &Metadata,
+        languages: &Languages,
+    ) -> Option<FileStat> {
+        // Without a modification time the file counts as the oldest one.
+        let mtime = metadata.modified().unwrap_or(UNIX_EPOCH);
+        let mtime = SystemTime::now()
+            .duration_since(mtime)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut stat = FileStat {
+            path: rel.to_path_buf(),
+            language: None,
+            category: Category::default(),
+            variant: languages::variant(file),
+            lines: Lines::default(),
+            bytes: metadata.len(),
+            binary: false,
+            modified: mtime,
+        };
+
+        match languages.detect(file) {
+            Some(language) => match count_lines(file, &language.syntax).ok()? {
+                Some(lc) => {
+                    stat.language = Some(language.name.clone());
+                    stat.category = language.category;
+                    stat.lines = lc;
+                }
+                None => stat.binary = true,
+            },
+            None => stat.binary = looks_binary(file),
+        }
+        Some(stat)
+    }
+
+    /// Counts a file that is already in memory, such as a blob of a git
+    /// commit. Its modification time is unknown and left at zero.
+    pub fn from_contents(rel: &Path, contents: Vec<u8>, languages: &Languages) -> FileStat {
+        let mut stat = FileStat {
+            path: rel.to_path_buf(),
+            language: None,
+            category: Category::default(),
+            variant: languages::variant(rel),
+            lines: Lines::default(),
+            bytes: contents.len() as u64,
+            binary: detect::is_binary(&contents),
+            modified: 0,
+        };
 
-            if file.file_name().to_string_lossy().matches(".").count() > 1 {
-                continue;
+        if let Some(language) = languages.detect_contents(rel, &contents) {
+            if !stat.binary {
+                stat.language = Some(language.name.clone());
+                stat.category = language.category;
+                stat.lines = lines::classify(&detect::decode(contents), &language.syntax);
             }
+        }
+        stat
+    }
+}
+
+/// Result of scanning a project: every file and directory found, and the
+/// per-language totals of the files.
+#[derive(Default)]
+pub struct Scan {
+    pub file_counts: HashMap<String, LangStats>,
+    pub times: HashMap<String, u64>,
+    pub binaries: Binaries,
+    pub files: Vec<FileStat>,
+    pub dirs: Vec<PathBuf>,
+}
 
-            let t = Path::new(file.file_name())
-                .extension()
-                .and_then(OsStr::to_str);
+impl Scan {
+    pub fn new(files: Vec<FileStat>, dirs: Vec<PathBuf>) -> Scan {
+        let mut scan = Scan {
+            files,
+            dirs,
+            ..Scan::default()
+        };
 
-            let ext = match t {
-                Some(ext) => ext,
-                None => "Undefined",
+        for file in &scan.files {
+            if file.binary {
+                scan.binaries.files += 1;
+                scan.binaries.bytes += file.bytes;
+            }
+            let language = match &file.language {
+                Some(language) => language,
+                None => continue,
             };
 
-            let extensions: Vec<&str> = ext_map.keys().cloned().collect();
-            if extensions.iter().any(|e| ext.eq(*e)) {
-                let mtime = file.metadata().unwrap().modified().expect("0");
-                let mtime = SystemTime::now()
-                    .duration_since(mtime)
-                    .expect("File time error")
-                    .as_secs();
-
-                times.insert(file.file_name().to_str().expect("msg").to_string(), mtime);
-                let size = file.metadata().unwrap().len();
-                let lc = count_lines(file.path());
-                proj_size += size;
-
-                if let Some(extension) = ext_map.get(&ext) {
-                    if let Some(count) = file_counts.get(extension) {
-                        file_counts.insert(
-                            extension.to_string(),
-                            (count.0 + 1, count.1 + lc, count.2 + size),
-                        );
-                    } else {
-                        file_counts.insert(extension.to_string(), (1, lc, size));
-                    }
-                }
+            if let Some(name) = file.path.file_name() {
+                scan.times
+                    .insert(name.to_string_lossy().into_owned(), file.modified);
             }
+
+            let count = scan.file_counts.entry(language.clone()).or_default();
+            count.category = file.category;
+            count.files += 1;
+            match file.variant {
+                Variant::Test => count.tests += 1,
+                Variant::Minified => count.minified += 1,
+                Variant::Source => {}
+            }
+            count.lines.add(&file.lines);
+            count.bytes += file.bytes;
         }
+        scan
+    }
+
+    /// Restricts the scan to the files under `dir`, relative to the project root.
+    pub fn subtree(&self, dir: &Path) -> Scan {
+        let files = self.files.iter().filter(|f| f.path.starts_with(dir));
+        let dirs = self.dirs.iter().filter(|d| d.starts_with(dir));
+        Scan::new(files.cloned().collect(), dirs.cloned().collect())
+    }
+
+    /// Names of the `n` most recently modified files.
+    pub fn recent(&self, n: usize) -> Vec<String> {
+        let mut count_time: Vec<(&String, &u64)> = self.times.iter().collect();
+        count_time.sort_by(|a, b| a.1.cmp(b.1));
+        count_time.iter().take(n).map(|i| i.0.to_string()).collect()
     }
-    (file_counts, proj_size, times)
 }
 
-pub fn get_percentages(
-    stats: &HashMap<String, (u32, u32, u64)>,
-    proj_size: u64,
-) -> HashMap<String, f64> {
-    let mut lang: HashMap<String, f64> = HashMap::new();
+/// Per-thread visitor of the parallel walk. Every walker thread collects its
+/// own files, which are merged once the thread is done.
+struct Collector<'s> {
+    files: Vec<FileStat>,
+    dirs: Vec<PathBuf>,
+    merged: &'s Mutex<(Vec<FileStat>, Vec<PathBuf>)>,
+    root: &'s Path,
+    languages: &'s Languages,
+}
+
+impl ParallelVisitor for Collector<'_> {
+    fn visit(&mut self, entry: Result<DirEntry, ignore::Error>) -> WalkState {
+        let file = match entry {
+            Ok(file) => file,
+            Err(_) => return WalkState::Continue,
+        };
+        let metadata = match file.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => return WalkState::Continue,
+        };
+        let rel = file.path().strip_prefix(self.root).unwrap_or(file.path());
+
+        if metadata.is_file() {
+            if let Some(stat) = FileStat::read(file.path(), rel, &metadata, self.languages) {
+                self.files.push(stat);
+            }
+        } else if metadata.is_dir() && file.depth() > 0 {
+            self.dirs.push(rel.to_path_buf());
+        }
+        WalkState::Continue
+    }
+}
+
+impl Drop for Collector<'_> {
+    fn drop(&mut self) {
+        let mut merged = self.merged.lock().unwrap();
+        merged.0.append(&mut self.files);
+        merged.1.append(&mut self.dirs);
+    }
+}
+
+struct CollectorBuilder<'s> {
+    merged: &'s Mutex<(Vec<FileStat>, Vec<PathBuf>)>,
+    root: &'s Path,
+    languages: &'s Languages,
+}
+
+impl<'s> ParallelVisitorBuilder<'s> for CollectorBuilder<'s> {
+    fn build(&mut self) -> Box<dyn ParallelVisitor + 's> {
+        Box::new(Collector {
+            files: Vec::new(),
+            dirs: Vec::new(),
+            merged: self.merged,
+            root: self.root,
+            languages: self.languages,
+        })
+    }
+}
+
+/// Walks `path` and counts every file on `threads` threads (0 picks one per CPU).
+/// With `ignore`, files excluded by `.gitignore` files, `.git/info/exclude` or
+/// the global git excludes file are skipped.
+pub fn get_stats(path: &str, ignore: bool, languages: &Languages, threads: usize) -> Scan {
+    let merged = Mutex::new((Vec::new(), Vec::new()));
+    let mut builder = CollectorBuilder {
+        merged: &merged,
+        root: Path::new(path),
+        languages,
+    };
 
-    for file in stats {
-        let (_, _, b) = file.1;
-        let ps = *b as f64 / proj_size as f64;
-        lang.insert(file.0.to_string(), ps * 100.0);
+    walker(path, ignore)
+        .threads(threads)
+        .build_parallel()
+        .visit(&mut builder);
+
+    let (files, dirs) = merged.into_inner().unwrap();
+    Scan::new(files, dirs)
+}
+
+/// Directory walker over `path` that skips `.git`, honoring git's ignore rules
+/// (nested `.gitignore` files, negations, `.git/info/exclude` and
+/// `core.excludesFile`) when `ignore` is set.
+pub fn walker(path: &str, ignore: bool) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(path);
+    builder
+        .standard_filters(false)
+        .git_ignore(ignore)
+        .git_exclude(ignore)
+        .git_global(ignore)
+        .parents(ignore)
+        .require_git(false)
+        .filter_entry(|file| file.file_name() != ".git");
+    builder
+}
+
+pub fn get_percentages(stats: &HashMap<String, LangStats>) -> HashMap<String, f64> {
+    let counted: Vec<(&String, &LangStats)> = stats
+        .iter()
+        .filter(|(_, s)| matches!(s.category, Category::Programming | Category::Markup))
+        .collect();
+    let total: u64 = counted.iter().map(|(_, s)| s.bytes).sum();
+    // Only empty files, so there is nothing to share out.
+    if total == 0 {
+        return HashMap::new();
+    }
+
+    let mut lang: HashMap<String, f64> = HashMap::new();
+    for (name, s) in counted {
+        let ps = s.bytes as f64 / total as f64;
+        lang.insert(name.to_string(), ps * 100.0);
     }
 
     lang
 }
+
+/// Formats a byte count with a binary unit, e.g. `45.6 KB`.
+pub fn human_bytes(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < units.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("pstat-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (file, contents) in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn honors_nested_gitignores() {
+        let root = project(
+            "ignore",
+            &[
+                (".gitignore", "*.log\nbuild/\n"),
+                ("main.rs", "fn main() {}\n"),
+                ("debug.log", "noise\n"),
+                ("build/out.rs", "fn generated() {}\n"),
+                ("web/.gitignore", "*.js\n!keep.js\n"),
+                ("web/app.js", "run();\n"),
+                ("web/keep.js", "keep();\n"),
+                (".git/info/exclude", "secret.py\n"),
+                ("secret.py", "password = 1\n"),
+            ],
+        );
+        let languages = Languages::builtin();
+        let path = root.to_str().unwrap();
+
+        let scan = get_stats(path, true, &languages, 2);
+        let stats = scan.file_counts;
+        assert_eq!(stats["Rust"].files, 1);
+        assert_eq!(stats["JavaScript"].files, 1);
+        assert!(!stats.contains_key("Python"));
+        assert_eq!(scan.files.len(), 4);
+        assert_eq!(scan.dirs, vec![PathBuf::from("web")]);
+
+        let stats = get_stats(path, false, &languages, 2).file_counts;
+        assert_eq!(stats["Rust"].files, 2);
+        assert_eq!(stats["JavaScript"].files, 2);
+        assert_eq!(stats["Python"].files, 1);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn shares_nothing_without_bytes() {
+        let stats = |bytes| {
+            let rust = LangStats {
+                files: 1,
+                bytes,
+                ..LangStats::default()
+            };
+            HashMap::from([("Rust".to_string(), rust)])
+        };
+        assert!(get_percentages(&stats(0)).is_empty());
+        assert_eq!(get_percentages(&stats(10))["Rust"], 100.0);
+    }
+
+    #[test]
+    fn skips_files_that_cant_be_read() {
+        let root = project("unreadable", &[("gone.rs", "fn main() {}\n")]);
+        let file = root.join("gone.rs");
+        let metadata = fs::metadata(&file).unwrap();
+        fs::remove_file(&file).unwrap();
+
+        let languages = Languages::builtin();
+        let rust = languages.detect(&file).unwrap();
+        assert!(count_lines(&file, &rust.syntax).is_err());
+        let stat = FileStat::read(&file, Path::new("gone.rs"), &metadata, &languages);
+        assert!(stat.is_none());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -4,38 +4,271 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     io::{self, Stdout},
+    path::PathBuf,
+    thread::{self, JoinHandle},
+    time::Duration,
 };
 use tui::{
     backend::CrosstermBackend,
     layout::{Constraint, Layout},
     style::{Color, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Tabs},
+    widgets::{Block, Borders, ListState, TableState, Tabs},
     Frame, Terminal,
 };
 
+use crate::app::{GitState, Log};
 use crate::ui;
+use pstat::activity;
+use pstat::authors::{self, AuthorSort, Contribution};
+use pstat::backend::{CommitDetail, GitBackend, LogFilter};
+use pstat::branches::BranchSort;
+use pstat::churn::{self, Change, HotspotSort};
+use pstat::history::{self, History, Sampling};
+use pstat::languages::Languages;
+use pstat::ownership::{self, Ownership};
+use pstat::report::Report;
+use pstat::stats::{get_percentages, Binaries, LangStats, Scan};
+use pstat::tree::{self, Node};
+
+/// The panels of the Git tab with a cursor, in the order `Tab` moves through
+/// them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GitPanel {
+    Status,
+    Log,
+    Branches,
+}
+
+impl GitPanel {
+    fn next(self) -> GitPanel {
+        match self {
+            GitPanel::Status => GitPanel::Log,
+            GitPanel::Log => GitPanel::Branches,
+            GitPanel::Branches => GitPanel::Status,
+        }
+    }
+}
+
+/// Git analyses running on background threads, so that the TUI stays
+/// responsive while a large repository is read. Each one fills the field of
+/// the same name in `TApp` once it is done.
+#[derive(Default)]
+pub struct Pending {
+    pub ownership: Option<JoinHandle<Result<Ownership, String>>>,
+    pub trends: Option<JoinHandle<Result<History, String>>>,
+    pub changes: Option<JoinHandle<Result<Vec<Change>, String>>>,
+    pub contributions: Option<JoinHandle<Result<Vec<Contribution>, String>>>,
+    pub activity: Option<JoinHandle<Result<Vec<activity::Commit>, String>>>,
+}
+
+impl Pending {
+    fn is_empty(&self) -> bool {
+        self.ownership.is_none()
+            && self.trends.is_none()
+            && self.changes.is_none()
+            && self.contributions.is_none()
+            && self.activity.is_none()
+    }
+}
+
+/// Moves the result of a finished background thread into `slot`, and returns
+/// whether there was one.
+fn finish<T>(
+    pending: &mut Option<JoinHandle<Result<T, String>>>,
+    slot: &mut Option<Result<T, String>>,
+) -> bool {
+    if !pending.as_ref().is_some_and(|handle| handle.is_finished()) {
+        return false;
+    }
+    if let Some(handle) = pending.take() {
+        *slot = Some(
+            handle
+                .join()
+                .unwrap_or_else(|_| Err(String::from("Reading the repository failed"))),
+        );
+    }
+    true
+}
 
 pub struct TApp {
-    pub scroll: (u16, u16),
-    pub status_scroll: (u16, u16),
-    pub tree: String,
+    /// Cursor of the Git Status panel.
+    pub status_state: ListState,
+    pub scan: Scan,
+    pub tree: Node,
+    pub expanded: HashSet<PathBuf>,
+    pub tree_state: ListState,
+    pub filter: Option<PathBuf>,
+    /// Blamed lines per file, read when the ownership panel is first shown.
+    pub ownership: Option<Result<Ownership, String>>,
+    /// Whether the ownership panel replaces the recently modified files.
+    pub show_ownership: bool,
+    /// Analyses still running in the background.
+    pub pending: Pending,
+    /// Snapshot the File Stats table shows deltas against.
+    pub baseline: Option<Report>,
+    pub languages: Languages,
+    /// Lines of code over time, read when the Trends tab is first opened.
+    pub trends: Option<Result<History, String>>,
+    /// Commits per author, read when the Authors tab is first opened.
+    pub contributions: Option<Result<Vec<Contribution>, String>>,
+    /// Commit times for the activity panel, read when the Git tab is first
+    /// opened.
+    pub activity: Option<Result<Vec<activity::Commit>, String>>,
+    pub author_sort: AuthorSort,
+    /// Index into `ui::TIME_RANGES`.
+    pub author_range: usize,
+    pub authors_state: TableState,
+    /// Whose commits the activity panel counts, 0 for everyone and otherwise
+    /// the position of the author by number of commits, starting at 1.
+    pub activity_author: usize,
+    /// Whether the activity panel shows the punchcard instead of the calendar.
+    pub punchcard: bool,
+    /// Changed files per commit, read when the Hotspots tab is first opened.
+    pub changes: Option<Result<Vec<Change>, String>>,
+    pub hotspot_sort: HotspotSort,
+    /// Index into `ui::TIME_RANGES`.
+    pub hotspot_range: usize,
+    pub hotspots_state: TableState,
     pub path: String,
-    pub branches: String,
-    pub log: String,
-    pub log_tree: String,
-    pub status: String,
+    /// Branches, status and log of the Git tab.
+    pub git: Result<GitState, String>,
+    pub backend: Option<Box<dyn GitBackend>>,
+    /// The Git tab panel the cursor keys move through.
+    pub git_focus: GitPanel,
+    pub branch_sort: BranchSort,
+    pub branches_state: TableState,
+    pub log_state: ListState,
+    /// The search the Git Log is filtered by.
+    pub log_search: String,
+    /// The search being typed after pressing `/`.
+    pub search: Option<String>,
+    /// Why the typed search couldn't be parsed.
+    pub search_error: Option<String>,
+    /// The commit opened from the Git Log.
+    pub detail: Option<Result<CommitDetail, String>>,
+    /// The file of the opened commit whose diff is shown.
+    pub detail_file: usize,
+    pub detail_scroll: u16,
     pub lang_stats: HashMap<String, f64>,
-    pub file_stats: HashMap<String, (u32, u32, u64)>,
+    pub file_stats: HashMap<String, LangStats>,
+    pub binaries: Binaries,
     pub file_time: Vec<String>,
     pub app_color: Color,
     pub tab: u32,
     pub verbose: bool,
 }
 
-fn ui(f: &mut Frame<CrosstermBackend<Stdout>>, app: &TApp) {
+impl TApp {
+    /// Picks up the results of finished background analyses, and returns
+    /// whether there were any to redraw.
+    fn collect(&mut self) -> bool {
+        let ownership = finish(&mut self.pending.ownership, &mut self.ownership);
+        let trends = finish(&mut self.pending.trends, &mut self.trends);
+        let changes = finish(&mut self.pending.changes, &mut self.changes);
+        let contributions = finish(&mut self.pending.contributions, &mut self.contributions);
+        let activity = finish(&mut self.pending.activity, &mut self.activity);
+        ownership || trends || changes || contributions || activity
+    }
+
+    /// The node under the cursor of the project tree.
+    pub fn selected(&self) -> Option<&Node> {
+        let rows = tree::rows(&self.tree, &self.expanded);
+        rows.get(self.tree_state.selected()?).map(|row| row.node)
+    }
+
+    /// Restricts the other Home tab panels to the files under `filter`, a path
+    /// relative to the project root, or shows the whole project again.
+    pub fn set_filter(&mut self, filter: Option<PathBuf>) {
+        let scan = self
+            .scan
+            .subtree(filter.as_deref().unwrap_or(&PathBuf::new()));
+        self.lang_stats = get_percentages(&scan.file_counts);
+        self.file_time = scan.recent(5);
+        self.file_stats = scan.file_counts;
+        self.binaries = scan.binaries;
+        self.filter = filter;
+    }
+
+    fn log_len(&self) -> usize {
+        self.git.as_ref().map_or(0, |git| git.log.commits.len())
+    }
+
+    /// Moves the cursor of the Git Log by `delta` commits, loading more of
+    /// them when it gets to the end.
+    fn move_log_cursor(&mut self, delta: isize) {
+        let target = self
+            .log_state
+            .selected()
+            .map_or(0, |i| i.saturating_add_signed(delta));
+        if let (Ok(git), Some(backend)) = (&mut self.git, &self.backend) {
+            while target + 1 >= git.log.commits.len() && !git.log.complete {
+                git.log.load_more(backend.as_ref());
+            }
+        }
+        let len = self.log_len();
+        if len > 0 {
+            self.log_state.select(Some(target.min(len - 1)));
+        }
+    }
+
+    /// Lists the commits matching `search` in the Git Log, or all of them if
+    /// it is empty.
+    fn search_log(&mut self, search: &str) -> Result<(), String> {
+        let filter = LogFilter::parse(search)?;
+        if let (Ok(git), Some(backend)) = (&mut self.git, &self.backend) {
+            git.log = Log::new(filter);
+            git.log.load_more(backend.as_ref());
+        }
+        self.log_search = search.trim().to_string();
+        self.log_state.select(None);
+        if self.git_focus == GitPanel::Log {
+            self.move_log_cursor(0);
+        }
+        Ok(())
+    }
+
+    /// Number of lines of the diff shown in the commit detail.
+    fn diff_len(&self) -> usize {
+        match &self.detail {
+            Some(Ok(detail)) => detail
+                .files
+                .get(self.detail_file)
+                .map_or(0, |file| file.lines.len()),
+            _ => 0,
+        }
+    }
+
+    /// Opens the commit under the cursor of the Git Log.
+    fn open_commit(&mut self) {
+        let id = match (&self.git, self.log_state.selected()) {
+            (Ok(git), Some(i)) => git.log.commits.get(i).map(|commit| commit.id.clone()),
+            _ => None,
+        };
+        if let (Some(id), Some(backend)) = (id, &self.backend) {
+            self.detail = Some(backend.commit(&id));
+            self.detail_file = 0;
+            self.detail_scroll = 0;
+        }
+    }
+
+    /// Shows the diff of the next or previous file of the opened commit.
+    fn cycle_file(&mut self, forward: bool) {
+        let files = match &self.detail {
+            Some(Ok(detail)) if !detail.files.is_empty() => detail.files.len(),
+            _ => return,
+        };
+        self.detail_file = match forward {
+            true => (self.detail_file + 1) % files,
+            false => (self.detail_file + files - 1) % files,
+        };
+        self.detail_scroll = 0;
+    }
+}
+
+fn ui(f: &mut Frame<CrosstermBackend<Stdout>>, app: &mut TApp) {
     let chunks = Layout::default()
         .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
         .split(f.size());
@@ -43,6 +276,9 @@ fn ui(f: &mut Frame<CrosstermBackend<Stdout>>, app: &TApp) {
     let tabs_list = vec![
         Spans::from(Span::styled("Home", Style::default().fg(Color::White))),
         Spans::from(Span::styled("Git", Style::default().fg(Color::White))),
+        Spans::from(Span::styled("Trends", Style::default().fg(Color::White))),
+        Spans::from(Span::styled("Authors", Style::default().fg(Color::White))),
+        Spans::from(Span::styled("Hotspots", Style::default().fg(Color::White))),
     ];
 
     let tabs = Tabs::new(tabs_list)
@@ -56,10 +292,12 @@ fn ui(f: &mut Frame<CrosstermBackend<Stdout>>, app: &TApp) {
         .select(app.tab as usize);
     f.render_widget(tabs, chunks[0]);
 
-    if app.tab == 0 {
-        ui::home_tab(f, chunks[1], &app);
-    } else {
-        ui::git_tab(f, chunks[1], &app);
+    match app.tab {
+        0 => ui::home_tab(f, chunks[1], app),
+        1 => ui::git_tab(f, chunks[1], app),
+        2 => ui::trends_tab(f, chunks[1], app),
+        3 => ui::authors_tab(f, chunks[1], app),
+        _ => ui::hotspots_tab(f, chunks[1], app),
     }
 }
 
@@ -70,49 +308,285 @@ pub fn setup_terminal(app: &mut TApp) -> Result<(), io::Error> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    terminal.draw(|f| ui(f, &app))?;
+    terminal.draw(|f| ui(f, app))?;
 
     loop {
+        if app.collect() {
+            terminal.draw(|f| ui(f, app))?;
+        }
+        // Wake up now and then to pick up background results once they are done.
+        if !app.pending.is_empty() && !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
         if let Event::Key(key) = event::read()? {
+            if let Some(search) = &mut app.search {
+                match key.code {
+                    KeyCode::Char(c) => search.push(c),
+                    KeyCode::Backspace => {
+                        search.pop();
+                    }
+                    KeyCode::Enter => {
+                        let search = search.clone();
+                        match app.search_log(&search) {
+                            Ok(()) => app.search = None,
+                            Err(e) => app.search_error = Some(e),
+                        }
+                    }
+                    KeyCode::Esc => app.search = None,
+                    _ => {}
+                }
+                terminal.draw(|f| ui(f, app))?;
+                continue;
+            }
             match key.code {
                 KeyCode::Down => {
-                    let lines: u16 = app.tree.lines().count().try_into().unwrap();
-                    let status_lines: u16 = app.status.lines().count().try_into().unwrap();
-
-                    if app.tab == 0 && lines - app.scroll.0 > 20 {
-                        app.scroll.0 += 1;
-                        terminal.draw(|f| ui(f, &app))?;
-                    } else if app.tab == 1 && status_lines - app.status_scroll.0 > 13 {
-                        app.status_scroll.0 += 1;
-                        terminal.draw(|f| ui(f, &app))?;
+                    let rows = tree::rows(&app.tree, &app.expanded).len();
+                    let cursor = app.tree_state.selected().unwrap_or(0);
+
+                    if app.tab == 0 && cursor + 1 < rows {
+                        app.tree_state.select(Some(cursor + 1));
+                        terminal.draw(|f| ui(f, app))?;
+                    } else if app.tab == 1 && app.detail.is_some() {
+                        if (app.detail_scroll as usize) + 1 < app.diff_len() {
+                            app.detail_scroll += 1;
+                            terminal.draw(|f| ui(f, app))?;
+                        }
+                    } else if app.tab == 1 && app.git_focus == GitPanel::Log {
+                        app.move_log_cursor(1);
+                        terminal.draw(|f| ui(f, app))?;
+                    } else if app.tab == 1 && app.git_focus == GitPanel::Branches {
+                        let cursor = app.branches_state.selected().unwrap_or(0);
+                        if cursor + 1 < app.git.as_ref().map_or(0, |git| git.branches.len()) {
+                            app.branches_state.select(Some(cursor + 1));
+                            terminal.draw(|f| ui(f, app))?;
+                        }
+                    } else if app.tab == 1 {
+                        let lines = ui::status_lines(app);
+                        let from = app.status_state.selected().map_or(0, |i| i + 1);
+                        if let Some(next) = (from..lines.len()).find(|&i| lines[i].0) {
+                            app.status_state.select(Some(next));
+                            terminal.draw(|f| ui(f, app))?;
+                        }
+                    } else if app.tab == 3 {
+                        let cursor = app.authors_state.selected().unwrap_or(0);
+                        if cursor + 1 < ui::author_rows(app).len() {
+                            app.authors_state.select(Some(cursor + 1));
+                            terminal.draw(|f| ui(f, app))?;
+                        }
+                    } else if app.tab == 4 {
+                        let cursor = app.hotspots_state.selected().unwrap_or(0);
+                        if cursor + 1 < ui::hotspot_rows(app).len() {
+                            app.hotspots_state.select(Some(cursor + 1));
+                            terminal.draw(|f| ui(f, app))?;
+                        }
                     }
                 }
                 KeyCode::Up => {
-                    if app.tab == 0 && app.scroll.0 > 0 {
-                        app.scroll.0 -= 1;
-                        terminal.draw(|f| ui(f, &app))?;
-                    } else if app.tab == 1 && app.status_scroll.0 > 0 {
-                        app.status_scroll.0 -= 1;
-                        terminal.draw(|f| ui(f, &app))?;
+                    let cursor = app.tree_state.selected().unwrap_or(0);
+
+                    if app.tab == 0 && cursor > 0 {
+                        app.tree_state.select(Some(cursor - 1));
+                        terminal.draw(|f| ui(f, app))?;
+                    } else if app.tab == 1 && app.detail.is_some() {
+                        if app.detail_scroll > 0 {
+                            app.detail_scroll -= 1;
+                            terminal.draw(|f| ui(f, app))?;
+                        }
+                    } else if app.tab == 1 && app.git_focus == GitPanel::Log {
+                        app.move_log_cursor(-1);
+                        terminal.draw(|f| ui(f, app))?;
+                    } else if app.tab == 1 && app.git_focus == GitPanel::Branches {
+                        let cursor = app.branches_state.selected().unwrap_or(0);
+                        if cursor > 0 {
+                            app.branches_state.select(Some(cursor - 1));
+                            terminal.draw(|f| ui(f, app))?;
+                        }
+                    } else if app.tab == 1 {
+                        let lines = ui::status_lines(app);
+                        let to = app.status_state.selected().unwrap_or(0);
+                        if let Some(previous) = (0..to).rev().find(|&i| lines[i].0) {
+                            app.status_state.select(Some(previous));
+                            terminal.draw(|f| ui(f, app))?;
+                        }
+                    } else if app.tab == 3 {
+                        let cursor = app.authors_state.selected().unwrap_or(0);
+                        if cursor > 0 {
+                            app.authors_state.select(Some(cursor - 1));
+                            terminal.draw(|f| ui(f, app))?;
+                        }
+                    } else if app.tab == 4 {
+                        let cursor = app.hotspots_state.selected().unwrap_or(0);
+                        if cursor > 0 {
+                            app.hotspots_state.select(Some(cursor - 1));
+                            terminal.draw(|f| ui(f, app))?;
+                        }
+                    }
+                }
+                KeyCode::Enter | KeyCode::Char(' ') if app.tab == 0 => {
+                    if let Some(node) = app.selected().filter(|node| node.is_dir) {
+                        let path = node.path.clone();
+                        if !app.expanded.remove(&path) {
+                            app.expanded.insert(path);
+                        }
+                        terminal.draw(|f| ui(f, app))?;
+                    }
+                }
+                KeyCode::Char('f') if app.tab == 0 => {
+                    if let Some(node) = app.selected() {
+                        let path = node.path.clone();
+                        if app.filter.as_ref() == Some(&path) {
+                            app.set_filter(None);
+                        } else {
+                            app.set_filter(Some(path));
+                        }
+                        terminal.draw(|f| ui(f, app))?;
+                    }
+                }
+                KeyCode::Char('o') if app.tab == 0 => {
+                    app.show_ownership = !app.show_ownership;
+                    terminal.draw(|f| ui(f, app))?;
+
+                    if app.show_ownership
+                        && app.ownership.is_none()
+                        && app.pending.ownership.is_none()
+                    {
+                        let (path, files) = (app.path.clone(), app.scan.files.clone());
+                        app.pending.ownership =
+                            Some(thread::spawn(move || ownership::blame(&path, &files)));
                     }
                 }
-                KeyCode::Right => {
-                    if app.tab == 0 {
-                        app.tab = 1;
-                        terminal.draw(|f| ui(f, &app))?;
+                KeyCode::Esc if app.tab == 0 && app.filter.is_some() => {
+                    app.set_filter(None);
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Right if app.tab < 4 => {
+                    app.tab += 1;
+                    terminal.draw(|f| ui(f, app))?;
+
+                    if app.tab == 2 && app.trends.is_none() && app.pending.trends.is_none() {
+                        let (path, languages) = (app.path.clone(), app.languages.clone());
+                        app.pending.trends = Some(thread::spawn(move || {
+                            history::history(&path, Sampling::Week, &languages)
+                        }));
+                    }
+                    if app.tab == 1 && app.activity.is_none() && app.pending.activity.is_none() {
+                        let path = app.path.clone();
+                        app.pending.activity =
+                            Some(thread::spawn(move || activity::commits(&path)));
+                    }
+                    if app.tab == 3
+                        && app.contributions.is_none()
+                        && app.pending.contributions.is_none()
+                    {
+                        let path = app.path.clone();
+                        app.pending.contributions =
+                            Some(thread::spawn(move || authors::contributions(&path)));
+                        app.authors_state.select(Some(0));
                     }
+                    if app.tab == 4 && app.changes.is_none() && app.pending.changes.is_none() {
+                        let path = app.path.clone();
+                        app.pending.changes = Some(thread::spawn(move || churn::changes(&path)));
+                        app.hotspots_state.select(Some(0));
+                    }
+                }
+                KeyCode::Left if app.tab > 0 => {
+                    app.tab -= 1;
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::PageDown if app.tab == 1 && app.detail.is_some() => {
+                    let last = app.diff_len().saturating_sub(1) as u16;
+                    app.detail_scroll = (app.detail_scroll + 20).min(last);
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::PageUp if app.tab == 1 && app.detail.is_some() => {
+                    app.detail_scroll = app.detail_scroll.saturating_sub(20);
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::PageDown if app.tab == 1 && app.git_focus == GitPanel::Log => {
+                    app.move_log_cursor(20);
+                    terminal.draw(|f| ui(f, app))?;
                 }
-                KeyCode::Left => {
-                    if app.tab == 1 {
-                        app.tab = 0;
-                        terminal.draw(|f| ui(f, &app))?;
+                KeyCode::PageUp if app.tab == 1 && app.git_focus == GitPanel::Log => {
+                    app.move_log_cursor(-20);
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Tab if app.tab == 1 && app.detail.is_some() => {
+                    app.cycle_file(true);
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::BackTab if app.tab == 1 && app.detail.is_some() => {
+                    app.cycle_file(false);
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Tab if app.tab == 1 => {
+                    app.git_focus = app.git_focus.next();
+                    if app.git_focus == GitPanel::Log && app.log_state.selected().is_none() {
+                        app.move_log_cursor(0);
                     }
+                    if app.git_focus == GitPanel::Branches {
+                        app.branches_state.select(Some(0));
+                    }
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Char('/') if app.tab == 1 && app.detail.is_none() => {
+                    app.search = Some(app.log_search.clone());
+                    app.search_error = None;
+                    terminal.draw(|f| ui(f, app))?;
                 }
-                KeyCode::Char('v') => {
-                    if app.tab == 1 {
-                        app.verbose = if app.verbose { false } else { true };
-                        terminal.draw(|f| ui(f, &app))?;
+                KeyCode::Enter
+                    if app.tab == 1 && app.git_focus == GitPanel::Log && app.detail.is_none() =>
+                {
+                    app.open_commit();
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Esc if app.tab == 1 && app.detail.is_some() => {
+                    app.detail = None;
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Esc if app.tab == 1 && !app.log_search.is_empty() => {
+                    // An empty search always parses.
+                    let _ = app.search_log("");
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Char('v') if app.tab == 1 => {
+                    app.verbose = !app.verbose;
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Char('a') if app.tab == 1 => {
+                    let authors = ui::activity_authors(app).len();
+                    app.activity_author = (app.activity_author + 1) % (authors + 1);
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Char('p') if app.tab == 1 => {
+                    app.punchcard = !app.punchcard;
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Char('s') if app.tab == 1 && app.detail.is_none() => {
+                    app.branch_sort = app.branch_sort.next();
+                    if app.git_focus == GitPanel::Branches {
+                        app.branches_state.select(Some(0));
                     }
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Char('s') if app.tab == 3 => {
+                    app.author_sort = app.author_sort.next();
+                    app.authors_state.select(Some(0));
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Char('r') if app.tab == 3 => {
+                    app.author_range = (app.author_range + 1) % ui::TIME_RANGES.len();
+                    app.authors_state.select(Some(0));
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Char('s') if app.tab == 4 => {
+                    app.hotspot_sort = app.hotspot_sort.next();
+                    app.hotspots_state.select(Some(0));
+                    terminal.draw(|f| ui(f, app))?;
+                }
+                KeyCode::Char('r') if app.tab == 4 => {
+                    app.hotspot_range = (app.hotspot_range + 1) % ui::TIME_RANGES.len();
+                    app.hotspots_state.select(Some(0));
+                    terminal.draw(|f| ui(f, app))?;
                 }
                 KeyCode::Char('q') => {
                     break;
@@ -0,0 +1,225 @@
+use crate::lines::Lines;
+use crate::stats::Scan;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// A file or directory of the project tree. Directories carry the totals of
+/// everything below them.
+#[derive(Clone, Debug, Default)]
+pub struct Node {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub children: Vec<Node>,
+    pub language: Option<String>,
+    pub files: u32,
+    pub lines: Lines,
+    pub bytes: u64,
+}
+
+impl Node {
+    fn dir(name: &str, path: PathBuf) -> Node {
+        Node {
+            name: name.to_string(),
+            path,
+            is_dir: true,
+            ..Node::default()
+        }
+    }
+
+    /// Sums up files, lines and bytes of every directory and sorts children by name.
+    fn finish(&mut self) {
+        if !self.is_dir {
+            return;
+        }
+        self.files = 0;
+        self.lines = Lines::default();
+        self.bytes = 0;
+
+        for child in self.children.iter_mut() {
+            child.finish();
+            self.files += child.files;
+            self.lines.add(&child.lines);
+            self.bytes += child.bytes;
+        }
+        self.children
+            .sort_by_key(|c| (c.name.to_lowercase(), c.name.clone()));
+    }
+}
+
+/// A line of the interactive tree: a node and the box-drawing prefix in front of it.
+pub struct Row<'a> {
+    pub prefix: String,
+    pub node: &'a Node,
+}
+
+/// Flattens the tree into its visible rows, descending only into the
+/// directories in `expanded`. The root is always the first row.
+pub fn rows<'a>(tree: &'a Node, expanded: &HashSet<PathBuf>) -> Vec<Row<'a>> {
+    let mut rows = vec![Row {
+        prefix: String::new(),
+        node: tree,
+    }];
+    if expanded.contains(&tree.path) {
+        visible(tree, "", expanded, &mut rows);
+    }
+    rows
+}
+
+fn visible<'a>(node: &'a Node, prefix: &str, expanded: &HashSet<PathBuf>, rows: &mut Vec<Row<'a>>) {
+    for (i, child) in node.children.iter().enumerate() {
+        let last = i + 1 == node.children.len();
+        let (branch, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+
+        rows.push(Row {
+            prefix: format!("{}{}", prefix, branch),
+            node: child,
+        });
+        if child.is_dir && expanded.contains(&child.path) {
+            visible(child, &format!("{}{}", prefix, indent), expanded, rows);
+        }
+    }
+}
+
+/// A directory while the tree is built, with its subdirectories by name so
+/// that finding one doesn't search its siblings.
+#[derive(Default)]
+struct Dir {
+    dirs: HashMap<String, Dir>,
+    files: Vec<Node>,
+}
+
+impl Dir {
+    /// Walks down to the directory `path`, creating missing ones.
+    fn dir(&mut self, path: &Path) -> &mut Dir {
+        let mut dir = self;
+        for part in path.iter() {
+            dir = dir
+                .dirs
+                .entry(part.to_string_lossy().into_owned())
+                .or_default();
+        }
+        dir
+    }
+
+    fn into_node(self, name: &str, path: PathBuf) -> Node {
+        let mut node = Node::dir(name, path);
+        for (name, dir) in self.dirs {
+            let path = node.path.join(&name);
+            node.children.push(dir.into_node(&name, path));
+        }
+        node.children.extend(self.files);
+        node
+    }
+}
+
+/// Builds the directory tree of a scan, rooted at `root`.
+pub fn build(root: &str, scan: &Scan) -> Node {
+    let mut dirs = Dir::default();
+    for dir in &scan.dirs {
+        dirs.dir(dir);
+    }
+    for file in &scan.files {
+        let name = file.path.file_name().unwrap_or_default().to_string_lossy();
+        let parent = file.path.parent().unwrap_or(Path::new(""));
+        dirs.dir(parent).files.push(Node {
+            name: name.into_owned(),
+            path: file.path.clone(),
+            is_dir: false,
+            children: Vec::new(),
+            language: file.language.clone(),
+            files: 1,
+            lines: file.lines,
+            bytes: file.bytes,
+        });
+    }
+
+    let mut tree = dirs.into_node(root, PathBuf::new());
+    tree.finish();
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::Languages;
+    use crate::stats::FileStat;
+
+    fn tree() -> Node {
+        let languages = Languages::builtin();
+        let files = [
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/ui/mod.rs", "// Drawing.\nfn draw() {}\n\n"),
+            ("README.md", "# Demo\n"),
+        ]
+        .iter()
+        .map(|(path, contents)| {
+            FileStat::from_contents(Path::new(path), contents.as_bytes().to_vec(), &languages)
+        })
+        .collect();
+        let dirs = ["docs", "src", "src/ui"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        build("demo", &Scan::new(files, dirs))
+    }
+
+    #[test]
+    fn builds_tree_with_totals() {
+        let tree = tree();
+        let names: Vec<&str> = tree.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["docs", "README.md", "src"]);
+        assert_eq!(
+            (tree.name.as_str(), tree.files, tree.bytes),
+            ("demo", 3, 46)
+        );
+
+        let docs = &tree.children[0];
+        assert!(docs.is_dir && docs.children.is_empty());
+        assert_eq!(docs.files, 0);
+
+        let src = &tree.children[2];
+        assert_eq!(src.path, Path::new("src"));
+        assert_eq!(src.files, 2);
+        assert_eq!(
+            (src.lines.code, src.lines.comments, src.lines.blanks),
+            (2, 1, 1)
+        );
+        let ui = &src.children[1];
+        assert_eq!(ui.children[0].path, Path::new("src/ui/mod.rs"));
+        assert_eq!(ui.children[0].language.as_deref(), Some("Rust"));
+    }
+
+    #[test]
+    fn draws_expanded_directories() {
+        let tree = tree();
+        let collapsed = rows(&tree, &HashSet::new());
+        assert_eq!(collapsed.len(), 1);
+
+        let expanded = HashSet::from([
+            PathBuf::new(),
+            PathBuf::from("src"),
+            PathBuf::from("src/ui"),
+        ]);
+        let lines: Vec<String> = rows(&tree, &expanded)
+            .iter()
+            .map(|row| format!("{}{}", row.prefix, row.node.name))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "demo",
+                "├── docs",
+                "├── README.md",
+                "└── src",
+                "    ├── main.rs",
+                "    └── ui",
+                "        └── mod.rs",
+            ]
+        );
+    }
+}
@@ -1,74 +1,586 @@
-use crate::terminal::TApp;
+use crate::terminal::{GitPanel, TApp};
+use pstat::activity::{self, Calendar};
+use pstat::authors::{self, Author};
+use pstat::backend::{LineChanges, StatusEntry, StatusGroup};
+use pstat::branches;
+use pstat::churn::{self, Hotspot};
+use pstat::dates;
+use pstat::history::History;
+use pstat::languages;
+use pstat::ownership;
+use pstat::snapshot::signed;
+use pstat::stats::{human_bytes, LangStats};
+use pstat::tree;
 use std::{
     cmp::Ordering,
     io::{self, Stdout},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
 };
 use tui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    widgets::{BarChart, Block, Borders, Cell, Paragraph, Row, Table, Wrap},
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+    text::{Span, Spans},
+    widgets::{
+        Axis, BarChart, Block, Borders, Cell, Chart, Dataset, GraphType, List, ListItem, ListState,
+        Paragraph, Row, Table, Wrap,
+    },
     Frame,
 };
 
-fn git_branch(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TApp) {
+const BAR_WIDTH: usize = 6;
+
+/// Panel title, naming the subtree the Home tab is filtered to.
+fn filtered(title: &str, app: &TApp) -> String {
+    match &app.filter {
+        Some(filter) => format!("{} - {}", title, filter.display()),
+        None => title.to_string(),
+    }
+}
+
+/// Centers a language name under its bar in the distribution chart.
+fn bar_label(name: &str, width: usize) -> String {
+    let len = name.chars().count();
+    if len >= width {
+        name.to_string()
+    } else {
+        format!("{}{}", " ".repeat((width - len) / 2), name)
+    }
+}
+
+/// Status glyph colors, like `git status` uses them.
+fn status_color(letter: char) -> Color {
+    match letter {
+        'A' => Color::Green,
+        'M' | 'T' => Color::Yellow,
+        'R' => Color::Cyan,
+        'D' => Color::Red,
+        'U' => Color::Magenta,
+        _ => Color::DarkGray,
+    }
+}
+
+/// The lines of the Git Status panel, with whether each one is a file the
+/// cursor can select.
+pub fn status_lines(app: &TApp) -> Vec<(bool, Spans<'static>)> {
+    let git = match &app.git {
+        Ok(git) => git,
+        Err(_) => return vec![(false, Spans::from("Not a git repository. No status found."))],
+    };
+
+    let mut lines = vec![(
+        false,
+        match &git.head {
+            Some(head) => Spans::from(format!("On branch {}", head)),
+            None => Spans::from("HEAD detached"),
+        },
+    )];
+    if git.status.is_empty() {
+        lines.push((false, Spans::from("nothing to commit, working tree clean")));
+        return lines;
+    }
+
+    let name = |entry: &StatusEntry| match &entry.from {
+        Some(from) => format!("{} -> {}", from, entry.path),
+        None => entry.path.clone(),
+    };
+    let width = git
+        .status
+        .iter()
+        .map(|entry| name(entry).chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for group in StatusGroup::ALL {
+        let entries: Vec<&StatusEntry> = git
+            .status
+            .iter()
+            .filter(|entry| entry.in_group(group))
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+
+        lines.push((false, Spans::from("")));
+        lines.push((
+            false,
+            Spans::from(Span::styled(
+                format!("{} ({})", group.name(), entries.len()),
+                Style::default()
+                    .fg(app.app_color)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        ));
+        for entry in entries {
+            let (letter, changes) = entry.change(group);
+            let mut spans = vec![
+                Span::styled(
+                    format!("  {} ", letter),
+                    Style::default().fg(status_color(letter)),
+                ),
+                Span::raw(format!("{:<width$} ", name(entry))),
+            ];
+            if entry.submodule {
+                spans.push(Span::styled(
+                    "submodule",
+                    Style::default().fg(Color::DarkGray),
+                ));
+            } else if changes != LineChanges::default() {
+                spans.push(Span::styled(
+                    format!("+{} ", changes.added),
+                    Style::default().fg(Color::Green),
+                ));
+                spans.push(Span::styled(
+                    format!("-{}", changes.removed),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            lines.push((true, Spans::from(spans)));
+        }
+    }
+    lines
+}
+
+fn git_branch(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &mut TApp) {
     let chunks = Layout::default()
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
         .split(area);
+    git_branches(f, chunks[0], app);
 
-    let branches = app.branches.clone();
-    let paragraph = Paragraph::new(branches)
-        .block(
-            Block::default()
+    let items: Vec<ListItem> = status_lines(app)
+        .into_iter()
+        .map(|(_, line)| ListItem::new(line))
+        .collect();
+    let list = List::new(items)
+        .block(focus_block(
+            "Git Status",
+            app.git_focus == GitPanel::Status,
+            app,
+        ))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(list, chunks[1], &mut app.status_state);
+}
+
+/// Local and remote-tracking branches with their upstream, last commit and
+/// whether they are merged into the default branch.
+fn git_branches(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &mut TApp) {
+    let (git, default) = match &app.git {
+        Ok(git) => (git, git.default_branch.as_deref().unwrap_or("HEAD")),
+        Err(_) => {
+            let block = focus_block("Git Branches", app.git_focus == GitPanel::Branches, app);
+            let paragraph = Paragraph::new("Not a git repository. No branches found.").block(block);
+            f.render_widget(paragraph, area);
+            return;
+        }
+    };
+    let title = format!(
+        "Git Branches - by {} (s: sort, merged into {})",
+        app.branch_sort.name(),
+        default
+    );
+    let block = focus_block(&title, app.git_focus == GitPanel::Branches, app);
+
+    let rows: Vec<Row> = branches::report(
+        &git.branches,
+        git.default_branch.as_deref(),
+        app.branch_sort,
+    )
+    .into_iter()
+    .map(|b| {
+        let name = match b.head {
+            true => Cell::from(format!("* {}", b.name)).style(Style::default().fg(Color::Green)),
+            false if b.remote => {
+                Cell::from(format!("  {}", b.name)).style(Style::default().fg(Color::Red))
+            }
+            false => Cell::from(format!("  {}", b.name)),
+        };
+        let tracking = match b.gone {
+            true => Cell::from(b.tracking()).style(Style::default().fg(Color::DarkGray)),
+            false => Cell::from(b.tracking()),
+        };
+        let merged = Cell::from(b.merge_state()).style(Style::default().fg(Color::Green));
+        Row::new(vec![
+            name,
+            tracking,
+            Cell::from(b.date.unwrap_or_default()),
+            Cell::from(b.author.unwrap_or_default()),
+            merged,
+            Cell::from(b.subject.unwrap_or_default()),
+        ])
+    })
+    .collect();
+
+    let table = Table::new(rows)
+        .header(
+            Row::new(vec![
+                "Branch", "Upstream", "Date", "Author", "Merged", "Subject",
+            ])
+            .style(Style::default().fg(app.app_color)),
+        )
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(&[
+            Constraint::Length(22),
+            Constraint::Length(24),
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Length(7),
+            Constraint::Min(0),
+        ]);
+    f.render_stateful_widget(table, area, &mut app.branches_state);
+}
+
+/// Border of the Git tab panels with a cursor, highlighted when the cursor
+/// keys move through it.
+fn focus_block(title: &str, focused: bool, app: &TApp) -> Block<'static> {
+    let color = if focused {
+        app.app_color
+    } else {
+        Color::DarkGray
+    };
+    Block::default()
+        .borders(Borders::ALL)
+        .title(title.to_string())
+        .border_style(Style::default().fg(color))
+}
+
+fn git_log(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &mut TApp) {
+    let area = match &app.search {
+        Some(search) => {
+            let chunks = Layout::default()
+                .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+                .split(area);
+            let title = match &app.search_error {
+                Some(e) => e.clone(),
+                None => String::from(
+                    "Search (author:, path:, since:, until:, Enter: apply, Esc: cancel)",
+                ),
+            };
+            let prompt =
+                Paragraph::new(format!("/{}", search)).block(focus_block(&title, true, app));
+            f.render_widget(prompt, chunks[1]);
+            chunks[0]
+        }
+        None => area,
+    };
+
+    let log = match &app.git {
+        Ok(git) => &git.log,
+        Err(_) => {
+            let block = focus_block("Git Log", app.git_focus == GitPanel::Log, app);
+            let paragraph = Paragraph::new("Not a git repository. No log found.").block(block);
+            f.render_widget(paragraph, area);
+            return;
+        }
+    };
+    let count = match (log.commits.len(), log.complete) {
+        (1, true) => String::from("1 commit"),
+        (n, true) => format!("{} commits", n),
+        (n, false) => format!("{}+ commits", n),
+    };
+    let title = match log.filter.is_empty() {
+        true => format!(
+            "Git Log - {} (/: search, Tab: focus, Enter: show commit)",
+            count
+        ),
+        false => format!(
+            "Git Log - {} matching {} (/: search, Esc: clear, Enter: show commit)",
+            count, app.log_search
+        ),
+    };
+    let block = focus_block(&title, app.git_focus == GitPanel::Log, app);
+    if log.commits.is_empty() {
+        let message = match (&log.error, log.filter.is_empty()) {
+            (Some(e), _) => format!("Couldn't read the log: {}", e),
+            (None, true) => String::from("No commits yet."),
+            (None, false) => String::from("No commits match the search."),
+        };
+        f.render_widget(Paragraph::new(message).block(block), area);
+        return;
+    }
+
+    let mut items = Vec::new();
+    if app.verbose {
+        for (commit, graph) in log.commits.iter().zip(&log.graph) {
+            items.push(ListItem::new(Spans::from(vec![
+                Span::styled(format!("{} ", graph), Style::default().fg(app.app_color)),
+                Span::styled(
+                    format!("{} ", commit.short_id()),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::raw(commit.summary.clone()),
+            ])));
+        }
+    } else {
+        for commit in &log.commits {
+            items.push(ListItem::new(vec![
+                Spans::from(Span::styled(
+                    format!("commit {}", commit.id),
+                    Style::default().fg(Color::Yellow),
+                )),
+                Spans::from(format!("Author: {} <{}>", commit.author, commit.email)),
+                Spans::from(format!(
+                    "Date:   {}",
+                    dates::rfc3339(commit.time, commit.offset).replacen('T', " ", 1)
+                )),
+                Spans::from(""),
+                Spans::from(format!("    {}", commit.summary)),
+                Spans::from(""),
+            ]));
+        }
+    }
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(list, area, &mut app.log_state);
+}
+
+fn diff_color(origin: char) -> Color {
+    match origin {
+        '+' => Color::Green,
+        '-' => Color::Red,
+        '@' => Color::Cyan,
+        _ => Color::Reset,
+    }
+}
+
+/// Message, people, parents and changed files of the commit opened from the
+/// log, with the diff of the selected file.
+fn commit_detail(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TApp) {
+    let detail = match &app.detail {
+        Some(Ok(detail)) => detail,
+        Some(Err(e)) => {
+            let block = Block::default()
                 .borders(Borders::ALL)
-                .title("Git Branches")
-                .border_style(Style::default().fg(app.app_color)),
+                .title("Commit (Esc: back)")
+                .border_style(Style::default().fg(app.app_color));
+            let message = format!("Couldn't read the commit: {}", e);
+            f.render_widget(Paragraph::new(message).block(block), area);
+            return;
+        }
+        None => return,
+    };
+    let info = &detail.info;
+
+    let person = |name: &str, email: &str, time: i64, offset: i32| {
+        format!(
+            "{} <{}>  {}",
+            name,
+            email,
+            dates::rfc3339(time, offset).replacen('T', " ", 1)
         )
-        .wrap(Wrap { trim: true });
-    f.render_widget(paragraph, chunks[0]);
+    };
+    let parents = match info.parents.is_empty() {
+        true => String::from("none, this is the first commit"),
+        false => info
+            .parents
+            .iter()
+            .map(|parent| &parent[..parent.len().min(7)])
+            .collect::<Vec<_>>()
+            .join(" "),
+    };
+    let mut header = vec![
+        Spans::from(Span::styled(
+            format!("commit {}", info.id),
+            Style::default().fg(Color::Yellow),
+        )),
+        Spans::from(format!("Parents:   {}", parents)),
+        Spans::from(format!(
+            "Author:    {}",
+            person(&info.author, &info.email, info.time, info.offset)
+        )),
+        Spans::from(format!(
+            "Committer: {}",
+            person(
+                &detail.committer,
+                &detail.committer_email,
+                detail.commit_time,
+                detail.commit_offset
+            )
+        )),
+        Spans::from(""),
+    ];
+    header.extend(
+        detail
+            .message
+            .lines()
+            .map(|line| Spans::from(format!("    {}", line))),
+    );
 
-    let status = app.status.clone();
-    let paragraph = Paragraph::new(status)
+    let height = (header.len() as u16 + 2).min(area.height * 2 / 5);
+    let chunks = Layout::default()
+        .constraints([Constraint::Length(height), Constraint::Min(0)].as_ref())
+        .split(area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Commit (Esc: back)")
+        .border_style(Style::default().fg(app.app_color));
+    f.render_widget(Paragraph::new(header).block(block), chunks[0]);
+
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+        .split(chunks[1]);
+
+    let items: Vec<ListItem> = detail
+        .files
+        .iter()
+        .map(|file| {
+            let path = match &file.from {
+                Some(from) => format!("{} -> {}", from, file.path),
+                None => file.path.clone(),
+            };
+            ListItem::new(Spans::from(vec![
+                Span::styled(
+                    format!("{} ", file.status),
+                    Style::default().fg(status_color(file.status)),
+                ),
+                Span::styled(
+                    format!("+{} ", file.changes.added),
+                    Style::default().fg(Color::Green),
+                ),
+                Span::styled(
+                    format!("-{} ", file.changes.removed),
+                    Style::default().fg(Color::Red),
+                ),
+                Span::raw(path),
+            ]))
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(app.detail_file));
+    let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Git Status")
+                .title(format!("Files ({})", detail.files.len()))
                 .border_style(Style::default().fg(app.app_color)),
         )
-        .wrap(Wrap { trim: true })
-        .scroll(app.status_scroll);
-    f.render_widget(paragraph, chunks[1]);
-}
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(list, panels[0], &mut state);
 
-fn git_log(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TApp) {
-    let log = if app.verbose {
-        app.log_tree.clone()
-    } else {
-        app.log.clone()
+    let file = detail.files.get(app.detail_file);
+    let lines: Vec<Spans> = match file {
+        Some(file) if file.binary => vec![Spans::from("Binary file")],
+        Some(file) => file
+            .lines
+            .iter()
+            .map(|line| {
+                let text = match line.origin {
+                    '@' => line.content.clone(),
+                    origin => format!("{}{}", origin, line.content),
+                };
+                Spans::from(Span::styled(
+                    text,
+                    Style::default().fg(diff_color(line.origin)),
+                ))
+            })
+            .collect(),
+        None => vec![Spans::from("No changes")],
     };
-    let paragraph = Paragraph::new(log)
+    let title = format!(
+        "Diff - {} (Up/Down: scroll, Tab: next file)",
+        file.map_or("", |file| file.path.as_str())
+    );
+    let paragraph = Paragraph::new(lines)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Git Log")
+                .title(title)
                 .border_style(Style::default().fg(app.app_color)),
         )
-        .wrap(Wrap { trim: true });
-    f.render_widget(paragraph, area);
+        .scroll((app.detail_scroll, 0));
+    f.render_widget(paragraph, panels[1]);
 }
 
-fn draw_tree(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TApp) {
-    let tree = app.tree.clone();
-    let paragraph = Paragraph::new(tree)
+fn draw_tree(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &mut TApp) {
+    let rows = tree::rows(&app.tree, &app.expanded);
+    let items: Vec<ListItem> = rows
+        .iter()
+        .map(|row| ListItem::new(tree_row(row, app)))
+        .collect();
+
+    let title = match &app.filter {
+        Some(filter) => format!("Project Tree - filtered to {}", filter.display()),
+        None => String::from("Project Tree"),
+    };
+    let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Project Tree")
+                .title(title)
                 .border_style(Style::default().fg(app.app_color)),
         )
-        .wrap(Wrap { trim: true })
-        .scroll(app.scroll);
-    f.render_widget(paragraph, area);
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(list, area, &mut app.tree_state);
+}
+
+/// A project tree line: directories with their totals, files with a marker in
+/// the color of their language.
+fn tree_row<'a>(row: &tree::Row<'a>, app: &TApp) -> Spans<'a> {
+    let node = row.node;
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut spans = vec![Span::styled(row.prefix.clone(), dim)];
+
+    if node.is_dir {
+        let arrow = if app.expanded.contains(&node.path) {
+            "▾ "
+        } else {
+            "▸ "
+        };
+        spans.push(Span::styled(arrow, Style::default().fg(app.app_color)));
+        spans.push(Span::styled(
+            node.name.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::styled(
+            format!(
+                "  {} file{}, {} lines, {}",
+                node.files,
+                if node.files == 1 { "" } else { "s" },
+                node.lines.total(),
+                human_bytes(node.bytes)
+            ),
+            dim,
+        ));
+    } else {
+        let marker = match &node.language {
+            Some(language) => Span::styled("● ", Style::default().fg(language_color(language))),
+            None => Span::styled("○ ", dim),
+        };
+        spans.push(marker);
+        spans.push(Span::raw(node.name.clone()));
+        if node.language.is_some() {
+            spans.push(Span::styled(format!("  {} lines", node.lines.total()), dim));
+        }
+    }
+    Spans::from(spans)
+}
+
+/// Color of a language's marker in the project tree, following GitHub's
+/// language colors for common languages.
+fn language_color(language: &str) -> Color {
+    if let Some((r, g, b)) = languages::color(language) {
+        return Color::Rgb(r, g, b);
+    }
+    // Spread the remaining languages over the basic terminal colors.
+    let palette = [
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::LightRed,
+        Color::LightGreen,
+        Color::LightYellow,
+        Color::LightBlue,
+        Color::LightMagenta,
+        Color::LightCyan,
+    ];
+    palette[languages::name_hash(language) % palette.len()]
 }
 
 fn draw_table(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TApp) {
@@ -76,44 +588,115 @@ fn draw_table(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TAp
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
         .split(area);
 
+    // Deltas only make sense against the whole project.
+    let baseline = app.baseline.as_ref().filter(|_| app.filter.is_none());
+    // Make room for the delta column.
+    let table_width = if baseline.is_some() { 75 } else { 65 };
+
     let chunks1 = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(65), Constraint::Percentage(35)].as_ref())
+        .constraints(
+            [
+                Constraint::Percentage(table_width),
+                Constraint::Percentage(100 - table_width),
+            ]
+            .as_ref(),
+        )
         .split(chunks[1]);
 
-    let mut count_time: Vec<(&String, &(u32, u32, u64))> = app.file_stats.iter().collect();
-    count_time.sort_by(|a, b| b.1 .2.cmp(&a.1 .2));
-
-    let rows = count_time.iter().map(|f| {
-        let cells = vec![
-            Cell::from(f.0.to_string().trim().to_owned()),
-            Cell::from(f.1 .0.to_string()),
-            Cell::from(f.1 .1.to_string()),
-            Cell::from(f.1 .2.to_string()),
-        ];
-        Row::new(cells)
-    });
+    let mut count_time: Vec<(&String, &LangStats)> = app.file_stats.iter().collect();
+    count_time.sort_by_key(|f| std::cmp::Reverse(f.1.bytes));
+
+    let delta = |name: &str, stats: &LangStats| {
+        let baseline = baseline?;
+        let old = baseline
+            .languages
+            .iter()
+            .find(|l| l.name == name)
+            .map_or(0, |l| l.lines.total() as i64);
+        let change = stats.lines.total() as i64 - old;
+        let color = match change {
+            0 => Color::DarkGray,
+            c if c > 0 => Color::Green,
+            _ => Color::Red,
+        };
+        Some(Cell::from(signed(change)).style(Style::default().fg(color)))
+    };
+
+    let mut rows: Vec<Row> = count_time
+        .iter()
+        .map(|f| {
+            let mut cells = vec![
+                Cell::from(f.0.to_string()),
+                Cell::from(f.1.files.to_string()),
+                Cell::from(f.1.lines.code.to_string()),
+                Cell::from(f.1.lines.comments.to_string()),
+                Cell::from(f.1.lines.blanks.to_string()),
+                Cell::from(f.1.tests.to_string()),
+                Cell::from(f.1.minified.to_string()),
+                Cell::from(f.1.bytes.to_string()),
+            ];
+            cells.extend(delta(f.0, f.1));
+            Row::new(cells)
+        })
+        .collect();
+
+    if app.binaries.files > 0 {
+        let binaries = app.binaries;
+        rows.push(
+            Row::new(vec![
+                Cell::from("Binary"),
+                Cell::from(binaries.files.to_string()),
+                Cell::from("-"),
+                Cell::from("-"),
+                Cell::from("-"),
+                Cell::from("-"),
+                Cell::from("-"),
+                Cell::from(binaries.bytes.to_string()),
+            ])
+            .style(Style::default().fg(Color::DarkGray)),
+        );
+    }
+
+    let mut header = vec![
+        "Language", "Files", "Code", "Comments", "Blanks", "Tests", "Min", "Size(B)",
+    ];
+    let mut title = filtered("File Stats", app);
+    if baseline.is_some() {
+        header.push("Δ Lines");
+        title.push_str(" - Δ vs baseline");
+    }
 
     let table = Table::new(rows)
         .header(
-            Row::new(vec!["Language", "No.Files", "No.Lines", "Size(B)"])
+            Row::new(header)
                 .style(Style::default().fg(app.app_color))
                 .bottom_margin(1),
         )
         .block(
             Block::default()
-                .title("File Stats")
+                .title(title)
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(app.app_color)),
         )
         .widths(&[
             Constraint::Length(12),
+            Constraint::Length(6),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Length(7),
+            Constraint::Length(6),
+            Constraint::Length(4),
             Constraint::Length(10),
-            Constraint::Length(10),
-            Constraint::Length(10),
+            Constraint::Length(8),
         ]);
     f.render_widget(table, chunks1[0]);
 
+    if app.show_ownership {
+        draw_ownership(f, chunks1[1], app);
+        return;
+    }
+
     let mut files = String::new();
     for (ind, f) in app.file_time.clone().iter().enumerate() {
         let temp = (ind + 1).to_string() + ". " + f + "\n";
@@ -124,13 +707,71 @@ fn draw_table(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TAp
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Recently Modified")
+                .title(filtered("Recently Modified", app))
                 .border_style(Style::default().fg(app.app_color)),
         )
         .wrap(Wrap { trim: true });
     f.render_widget(paragraph, chunks1[1]);
 }
 
+/// Who wrote the committed lines of the node selected in the project tree.
+fn draw_ownership(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TApp) {
+    let node = app.selected();
+    let title = match node {
+        Some(node) if !node.path.as_os_str().is_empty() => {
+            format!("Ownership - {}", node.path.display())
+        }
+        _ => String::from("Ownership"),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(app.app_color));
+
+    let ownership = match &app.ownership {
+        None => {
+            f.render_widget(Paragraph::new("Blaming files...").block(block), area);
+            return;
+        }
+        Some(Err(e)) => {
+            let message = format!("Not a git repository. No history found. ({})", e);
+            let paragraph = Paragraph::new(message).wrap(Wrap { trim: true });
+            f.render_widget(paragraph.block(block), area);
+            return;
+        }
+        Some(Ok(ownership)) => ownership,
+    };
+
+    let owners = ownership.owners(node.map_or(Path::new(""), |node| node.path.as_path()));
+    let total: u32 = owners.iter().map(|o| o.lines).sum();
+    if total == 0 {
+        f.render_widget(Paragraph::new("No committed lines.").block(block), area);
+        return;
+    }
+
+    let mut lines = vec![
+        Spans::from(vec![
+            Span::raw("Bus factor: "),
+            Span::styled(
+                ownership::bus_factor(&owners).to_string(),
+                Style::default().fg(app.app_color),
+            ),
+            Span::raw(format!(" of {} authors", owners.len())),
+        ]),
+        Spans::from(""),
+    ];
+    for owner in &owners {
+        lines.push(Spans::from(vec![
+            Span::styled(
+                format!("{:>5.1}% ", owner.lines as f64 * 100.0 / total as f64),
+                Style::default().fg(app.app_color),
+            ),
+            Span::raw(format!("{} ({})", owner.name, owner.lines)),
+        ]));
+    }
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
 fn draw_gauge(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TApp) {
     let chunks = Layout::default()
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
@@ -146,23 +787,23 @@ fn draw_gauge(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TAp
         }
     });
 
-    let mut data = Vec::new();
-    for lang in temp {
-        if *lang.1 as u64 >= 1 {
-            data.push((&lang.0[..], *lang.1 as u64));
-        }
-    }
+    let labels: Vec<(String, u64)> = temp
+        .iter()
+        .filter(|lang| *lang.1 as u64 >= 1)
+        .map(|lang| (bar_label(lang.0, BAR_WIDTH), *lang.1 as u64))
+        .collect();
+    let data: Vec<(&str, u64)> = labels.iter().map(|(l, v)| (&l[..], *v)).collect();
     let data = data.as_slice();
 
     let barchart = BarChart::default()
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Language Distribution")
+                .title(filtered("Language Distribution", app))
                 .border_style(Style::default().fg(app.app_color)),
         )
-        .data(&data)
-        .bar_width(6)
+        .data(data)
+        .bar_width(BAR_WIDTH as u16)
         .bar_gap(2)
         .value_style(Style::default().fg(Color::Black).bg(app.app_color))
         .label_style(Style::default().fg(Color::White))
@@ -171,7 +812,7 @@ fn draw_gauge(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TAp
     f.render_widget(barchart, chunks[0]);
 }
 
-pub fn home_tab(f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect, app: &TApp) {
+pub fn home_tab(f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect, app: &mut TApp) {
     let block = Block::default()
         .title("Project Stats")
         .borders(Borders::ALL)
@@ -189,19 +830,445 @@ pub fn home_tab(f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect, app: &TApp)
     draw_table(f, chunks[1], app);
 }
 
-pub fn git_tab(f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect, app: &TApp) {
+pub fn git_tab(f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect, app: &mut TApp) {
     let block = Block::default()
         .title("Git Stats")
         .borders(Borders::ALL)
         .border_style(Style::default().fg(app.app_color));
     f.render_widget(block, area);
 
+    if app.detail.is_some() {
+        let inner = Layout::default()
+            .margin(2)
+            .constraints([Constraint::Min(0)].as_ref())
+            .split(area);
+        commit_detail(f, inner[0], app);
+        return;
+    }
+
+    let rows = Layout::default()
+        .margin(2)
+        .constraints([Constraint::Min(0), Constraint::Length(11)].as_ref())
+        .split(area);
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .margin(2)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-        .split(area);
+        .split(rows[0]);
 
     git_branch(f, chunks[0], app);
     git_log(f, chunks[1], app);
+    git_activity(f, rows[1], app);
+}
+
+/// Names and emails of everyone who committed, most commits first, for the
+/// activity panel to cycle through.
+pub fn activity_authors(app: &TApp) -> Vec<(String, String)> {
+    match &app.activity {
+        Some(Ok(commits)) => activity::authors(commits),
+        _ => Vec::new(),
+    }
+}
+
+/// GitHub's contribution colors, from no commits to the busiest days.
+const ACTIVITY_COLORS: [Color; 5] = [
+    Color::DarkGray,
+    Color::Rgb(14, 68, 41),
+    Color::Rgb(0, 109, 50),
+    Color::Rgb(38, 166, 65),
+    Color::Rgb(57, 211, 83),
+];
+
+fn git_activity(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect, app: &TApp) {
+    let author = app
+        .activity_author
+        .checked_sub(1)
+        .and_then(|i| activity_authors(app).into_iter().nth(i));
+    let who = author
+        .as_ref()
+        .map_or("everyone", |(name, _)| name.as_str());
+    let commits: Vec<&activity::Commit> = match &app.activity {
+        Some(Ok(commits)) => commits
+            .iter()
+            .filter(|c| {
+                author
+                    .as_ref()
+                    .is_none_or(|(_, email)| c.email.eq_ignore_ascii_case(email))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let (title, lines) = if app.punchcard {
+        let title = format!(
+            "Commits by Weekday and Hour - {} (a: author, p: calendar)",
+            who
+        );
+        (title, punchcard_lines(&commits))
+    } else {
+        let calendar = activity::calendar(commits, now());
+        // Drop the oldest weeks when the panel is too narrow for all of them.
+        let fits = (area.width.saturating_sub(6) / 2) as usize;
+        let title = format!(
+            "Commit Activity - {} - {} commits in the last year (a: author, p: punchcard)",
+            who,
+            calendar.total()
+        );
+        (title, calendar_lines(&calendar, fits))
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(app.app_color));
+
+    let message = match &app.activity {
+        None => Some("Reading the git history...".to_string()),
+        Some(Err(e)) => Some(format!("Not a git repository. No history found. ({})", e)),
+        Some(Ok(_)) => None,
+    };
+    let paragraph = match message {
+        Some(message) => Paragraph::new(message),
+        None => Paragraph::new(lines),
+    };
+    f.render_widget(paragraph.block(block), area);
+}
+
+/// Weekday rows of the contribution calendar, under a row of month names,
+/// showing at most `weeks` of the most recent weeks.
+fn calendar_lines(calendar: &Calendar, weeks: usize) -> Vec<Spans<'static>> {
+    let first = calendar.weeks.len().saturating_sub(weeks);
+    let max = calendar.max();
+
+    let mut header = " ".repeat(4 + (calendar.weeks.len() - first) * 2);
+    for (week, month) in calendar.months().iter().enumerate().skip(first) {
+        let at = 4 + (week - first) * 2;
+        if let Some(month) = month.filter(|_| at + 3 <= header.len()) {
+            header.replace_range(at..at + 3, month);
+        }
+    }
+
+    let mut lines = vec![Spans::from(header)];
+    for (weekday, name) in activity::WEEKDAYS.iter().enumerate() {
+        let mut spans = vec![Span::raw(format!("{} ", name))];
+        for (week, counts) in calendar.weeks.iter().enumerate().skip(first) {
+            if calendar.day(week, weekday) > calendar.today {
+                break;
+            }
+            let color = ACTIVITY_COLORS[activity::level(counts[weekday], max)];
+            spans.push(Span::styled("■ ", Style::default().fg(color)));
+        }
+        lines.push(Spans::from(spans));
+    }
+
+    let mut legend = vec![Span::raw("Less ")];
+    for color in ACTIVITY_COLORS {
+        legend.push(Span::styled("■ ", Style::default().fg(color)));
+    }
+    legend.push(Span::raw("More"));
+    lines.push(Spans::from(legend));
+    lines
+}
+
+/// Weekday rows of commits per hour, under a row of hours.
+fn punchcard_lines(commits: &[&activity::Commit]) -> Vec<Spans<'static>> {
+    let hours = activity::punchcard(commits.iter().copied());
+    let max = hours.iter().flatten().copied().max().unwrap_or(0);
+
+    let header: String = (0..24).map(|hour| format!("{:<3}", hour)).collect();
+    let mut lines = vec![Spans::from(format!("    {}", header))];
+    for (name, counts) in activity::WEEKDAYS.iter().zip(hours) {
+        let mut spans = vec![Span::raw(format!("{} ", name))];
+        for count in counts {
+            let level = activity::level(count, max);
+            let dot = if level == 0 { "·  " } else { "●  " };
+            spans.push(Span::styled(
+                dot,
+                Style::default().fg(ACTIVITY_COLORS[level]),
+            ));
+        }
+        lines.push(Spans::from(spans));
+    }
+    lines
+}
+
+/// Number of languages the Trends chart shows, the rest are lumped together.
+const TREND_LANGUAGES: usize = 6;
+
+pub fn trends_tab(f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect, app: &TApp) {
+    let block = Block::default()
+        .title("Lines of Code per Week")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(app.app_color));
+
+    let message = match &app.trends {
+        None => Some("Reading the git history...".to_string()),
+        Some(Err(e)) => Some(format!("Not a git repository. No history found. ({})", e)),
+        Some(Ok(history)) if history.points.is_empty() => Some("No commits found.".to_string()),
+        Some(Ok(_)) => None,
+    };
+    match (message, &app.trends) {
+        (Some(message), _) => f.render_widget(Paragraph::new(message).block(block), area),
+        (None, Some(Ok(history))) => draw_trends(f, area, block, history),
+        _ => {}
+    }
+}
+
+/// Stacked line chart of the code lines of the biggest languages, the
+/// biggest one at the bottom.
+fn draw_trends(
+    f: &mut Frame<CrosstermBackend<Stdout>>,
+    area: Rect,
+    block: Block,
+    history: &History,
+) {
+    let last = &history.points[history.points.len() - 1];
+    let mut names: Vec<&String> = last.languages.keys().collect();
+    names.sort_by_key(|name| std::cmp::Reverse(last.languages[*name].code));
+    names.truncate(TREND_LANGUAGES);
+
+    let others = history
+        .points
+        .iter()
+        .any(|p| p.languages.keys().any(|name| !names.contains(&name)));
+    let layers = names.len() + usize::from(others);
+
+    // Running sums, so every line sits on top of the ones below it.
+    let mut stacks: Vec<Vec<(f64, f64)>> = vec![Vec::new(); layers];
+    let mut top: f64 = 0.0;
+    for point in &history.points {
+        let x = point.time as f64;
+        let mut sum = 0.0;
+        for (layer, name) in names.iter().enumerate() {
+            sum += point.languages.get(*name).map_or(0, |l| l.code) as f64;
+            stacks[layer].push((x, sum));
+        }
+        if others {
+            sum += point
+                .languages
+                .iter()
+                .filter(|(name, _)| !names.contains(name))
+                .map(|(_, l)| l.code as f64)
+                .sum::<f64>();
+            stacks[layers - 1].push((x, sum));
+        }
+        top = top.max(sum);
+    }
+
+    let datasets = stacks
+        .iter()
+        .enumerate()
+        .map(|(layer, data)| {
+            let (name, color) = match names.get(layer) {
+                Some(name) => (name.to_string(), language_color(name)),
+                None => ("Other".to_string(), Color::Gray),
+            };
+            Dataset::default()
+                .name(name)
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(color))
+                .data(data)
+        })
+        .collect();
+
+    let first = &history.points[0];
+    let (start, end) = (first.time as f64, last.time as f64);
+    let end = if end > start { end } else { start + 1.0 };
+    let middle = &history.points[history.points.len() / 2];
+    let top = top.max(1.0) * 1.05;
+
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(
+            Axis::default().bounds([start, end]).labels(
+                [&first.date, &middle.date, &last.date]
+                    .iter()
+                    .map(|date| Span::raw(date.to_string()))
+                    .collect(),
+            ),
+        )
+        .y_axis(
+            Axis::default().title("Code").bounds([0.0, top]).labels(
+                [0.0, top / 2.0, top]
+                    .iter()
+                    .map(|lines| Span::raw(format!("{:.0}", lines)))
+                    .collect(),
+            ),
+        )
+        .hidden_legend_constraints((Constraint::Ratio(1, 3), Constraint::Ratio(1, 2)));
+    f.render_widget(chart, area);
+}
+
+/// Time ranges the Authors and Hotspots tabs cycle through, as days back
+/// from now.
+pub const TIME_RANGES: [(&str, Option<i64>); 4] = [
+    ("All Time", None),
+    ("Last Year", Some(365)),
+    ("Last 90 Days", Some(90)),
+    ("Last 30 Days", Some(30)),
+];
+
+/// The authors of the selected time range, in the selected order.
+pub fn author_rows(app: &TApp) -> Vec<Author> {
+    let contributions = match &app.contributions {
+        Some(Ok(contributions)) => contributions,
+        _ => return Vec::new(),
+    };
+    authors::authors(
+        contributions,
+        since(app.author_range),
+        None,
+        app.author_sort,
+    )
+}
+
+/// Start of one of the `TIME_RANGES`, in seconds since the epoch.
+fn since(range: usize) -> Option<i64> {
+    TIME_RANGES[range].1.map(|days| now() - days * dates::DAY)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+pub fn authors_tab(f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect, app: &mut TApp) {
+    let (range, _) = TIME_RANGES[app.author_range];
+    let block = Block::default()
+        .title(format!(
+            "Authors - {} - by {} (s: sort, r: range)",
+            range,
+            app.author_sort.name()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(app.app_color));
+
+    let authors = author_rows(app);
+    let message = match &app.contributions {
+        None => Some("Reading the git history...".to_string()),
+        Some(Err(e)) => Some(format!("Not a git repository. No history found. ({})", e)),
+        Some(Ok(_)) if authors.is_empty() => Some("No commits found.".to_string()),
+        Some(Ok(_)) => None,
+    };
+    if let Some(message) = message {
+        f.render_widget(Paragraph::new(message).block(block), area);
+        return;
+    }
+
+    let rows: Vec<Row> = authors
+        .iter()
+        .map(|a| {
+            Row::new(vec![
+                Cell::from(a.name.clone()),
+                Cell::from(a.commits.to_string()),
+                Cell::from(format!("+{}", a.added)).style(Style::default().fg(Color::Green)),
+                Cell::from(format!("-{}", a.removed)).style(Style::default().fg(Color::Red)),
+                Cell::from(a.first.clone()),
+                Cell::from(a.last.clone()),
+                Cell::from(a.active_days.to_string()),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(rows)
+        .header(
+            Row::new(vec![
+                "Author", "Commits", "Added", "Removed", "First", "Last", "Days",
+            ])
+            .style(Style::default().fg(app.app_color))
+            .bottom_margin(1),
+        )
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(&[
+            Constraint::Length(24),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(5),
+        ]);
+    f.render_stateful_widget(table, area, &mut app.authors_state);
+}
+
+/// The changed files of the selected time range, in the selected order.
+pub fn hotspot_rows(app: &TApp) -> Vec<Hotspot> {
+    match &app.changes {
+        Some(Ok(changes)) => churn::hotspots(
+            changes,
+            &app.scan,
+            since(app.hotspot_range),
+            None,
+            app.hotspot_sort,
+        ),
+        _ => Vec::new(),
+    }
+}
+
+pub fn hotspots_tab(f: &mut Frame<CrosstermBackend<Stdout>>, area: Rect, app: &mut TApp) {
+    let (range, _) = TIME_RANGES[app.hotspot_range];
+    let block = Block::default()
+        .title(format!(
+            "Hotspots - {} - by {} (s: sort, r: range)",
+            range,
+            app.hotspot_sort.name()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(app.app_color));
+
+    let hotspots = hotspot_rows(app);
+    let message = match &app.changes {
+        None => Some("Reading the git history...".to_string()),
+        Some(Err(e)) => Some(format!("Not a git repository. No history found. ({})", e)),
+        Some(Ok(_)) if hotspots.is_empty() => Some("No changed files found.".to_string()),
+        Some(Ok(_)) => None,
+    };
+    if let Some(message) = message {
+        f.render_widget(Paragraph::new(message).block(block), area);
+        return;
+    }
+
+    // Files in the top half of the scores stand out in red, the next quarter in yellow.
+    let max = hotspots.iter().map(|h| h.score).max().unwrap_or(0);
+    let rows: Vec<Row> = hotspots
+        .iter()
+        .map(|h| {
+            let color = match h.score * 4 {
+                score if max > 0 && score >= max * 2 => Color::Red,
+                score if max > 0 && score >= max => Color::Yellow,
+                _ => Color::Reset,
+            };
+            Row::new(vec![
+                Cell::from(h.path.clone()).style(Style::default().fg(color)),
+                Cell::from(h.language.clone().unwrap_or_default()),
+                Cell::from(h.commits.to_string()),
+                Cell::from(format!("+{}", h.added)).style(Style::default().fg(Color::Green)),
+                Cell::from(format!("-{}", h.removed)).style(Style::default().fg(Color::Red)),
+                Cell::from(h.code.to_string()),
+                Cell::from(h.score.to_string()).style(Style::default().fg(color)),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(rows)
+        .header(
+            Row::new(vec![
+                "File", "Language", "Commits", "Added", "Removed", "Code", "Score",
+            ])
+            .style(Style::default().fg(app.app_color))
+            .bottom_margin(1),
+        )
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(&[
+            Constraint::Min(30),
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Length(10),
+        ]);
+    f.render_stateful_widget(table, area, &mut app.hotspots_state);
 }